@@ -135,6 +135,9 @@ impl CalculatorView {
 
         let mut tbox = TextBox::new("".to_owned());
         tbox.set_singleline(true);
+        tbox.set_filter(Some(CharFilter::Number));
+        tbox.set_pattern(Some(r"([-+]?(\d+\.?\d*|\.\d+))?"))
+            .unwrap();
         tbox.set(skryn::gui::properties::Property::Height(
             skryn::gui::properties::Unit::Pixel(40.0),
         ));
@@ -153,16 +156,6 @@ impl CalculatorView {
             skryn::gui::properties::Unit::Pixel(44.0),
         ));
         let mut addbutt = Button::new("+".to_owned());
-        addbutt.set(skryn::gui::properties::Property::Size(32));
-        addbutt.set(skryn::gui::properties::Property::TextAlign(
-            skryn::gui::properties::Align::Middle,
-        ));
-        addbutt.set(skryn::gui::properties::Property::Top(
-            skryn::gui::properties::Unit::Stretch(1.0),
-        ));
-        addbutt.set(skryn::gui::properties::Property::Bottom(
-            skryn::gui::properties::Unit::Stretch(1.0),
-        ));
         let tmpbox = tbox.clone();
         let tmpcalc = calc.clone();
         let tmphist = history.clone();
@@ -190,16 +183,6 @@ impl CalculatorView {
         hbox.append(Arc::new(Mutex::new(addbutt)));
 
         let mut subbutt = Button::new("-".to_owned());
-        subbutt.set(skryn::gui::properties::Property::Size(32));
-        subbutt.set(skryn::gui::properties::Property::TextAlign(
-            skryn::gui::properties::Align::Middle,
-        ));
-        subbutt.set(skryn::gui::properties::Property::Top(
-            skryn::gui::properties::Unit::Stretch(1.0),
-        ));
-        subbutt.set(skryn::gui::properties::Property::Bottom(
-            skryn::gui::properties::Unit::Stretch(1.0),
-        ));
         let tmpbox = tbox.clone();
         let tmpcalc = calc.clone();
         let tmphist = history.clone();
@@ -231,16 +214,6 @@ impl CalculatorView {
         hbox.append(Arc::new(Mutex::new(subbutt)));
 
         let mut mulbutt = Button::new("*".to_owned());
-        mulbutt.set(skryn::gui::properties::Property::Size(32));
-        mulbutt.set(skryn::gui::properties::Property::TextAlign(
-            skryn::gui::properties::Align::Middle,
-        ));
-        mulbutt.set(skryn::gui::properties::Property::Top(
-            skryn::gui::properties::Unit::Stretch(1.0),
-        ));
-        mulbutt.set(skryn::gui::properties::Property::Bottom(
-            skryn::gui::properties::Unit::Stretch(1.0),
-        ));
         let tmpbox = tbox.clone();
         let tmpcalc = calc.clone();
         let tmphist = history.clone();
@@ -272,16 +245,6 @@ impl CalculatorView {
         hbox.append(Arc::new(Mutex::new(mulbutt)));
 
         let mut divbutt = Button::new("/".to_owned());
-        divbutt.set(skryn::gui::properties::Property::Size(32));
-        divbutt.set(skryn::gui::properties::Property::TextAlign(
-            skryn::gui::properties::Align::Middle,
-        ));
-        divbutt.set(skryn::gui::properties::Property::Top(
-            skryn::gui::properties::Unit::Stretch(1.0),
-        ));
-        divbutt.set(skryn::gui::properties::Property::Bottom(
-            skryn::gui::properties::Unit::Stretch(1.0),
-        ));
         let tmpbox = tbox.clone();
         let tmpcalc = calc.clone();
         let tmphist = history.clone();
@@ -309,16 +272,6 @@ impl CalculatorView {
         hbox.append(Arc::new(Mutex::new(divbutt)));
 
         let mut eqlbutt = Button::new("=".to_owned());
-        eqlbutt.set(skryn::gui::properties::Property::Size(32));
-        eqlbutt.set(skryn::gui::properties::Property::TextAlign(
-            skryn::gui::properties::Align::Middle,
-        ));
-        eqlbutt.set(skryn::gui::properties::Property::Top(
-            skryn::gui::properties::Unit::Stretch(1.0),
-        ));
-        eqlbutt.set(skryn::gui::properties::Property::Bottom(
-            skryn::gui::properties::Unit::Stretch(1.0),
-        ));
         let tmpbox = tbox.clone();
         let tmpcalc = calc.clone();
         let tmphist = history.clone();
@@ -366,6 +319,18 @@ impl Alert {
 }
 
 fn main() {
+    //in debug builds edit the file while the calculator runs to restyle it
+    let css = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/calculator.css");
+    let _watcher = if cfg!(debug_assertions) {
+        Some(skryn::gui::css::watch(css))
+    } else {
+        match skryn::gui::css::load(css) {
+            Ok(sheet) => skryn::gui::style::Stylesheet::set(sheet),
+            Err(e) => eprintln!("{}:{}", css, e),
+        }
+        None
+    };
+
     let calc = CalculatorView::new();
 
     //Calc.push_num(1.0);
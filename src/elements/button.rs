@@ -4,8 +4,10 @@ use std::sync::Arc;
 use webrender::api::*;
 
 use crate::elements::element::*;
+use crate::elements::layout::*;
 use crate::gui::font;
 use crate::gui::properties;
+use crate::gui::style;
 
 pub struct Button {
     ext_id: u64,
@@ -16,6 +18,7 @@ pub struct Button {
     event_handlers: EventHandlers,
     drawn: u8,
     hovering: bool,
+    click: ClickState,
     enabled: bool,
 }
 
@@ -23,15 +26,12 @@ impl Button {
     pub fn new(s: String) -> Self {
         let mut props = properties::Properties::new();
         props.default();
+        //colors come from the theme
         props
-            .set(properties::Property::TextAlign(properties::Align::Middle))
-            .set(properties::Property::BgColor(ColorF::new(
-                0.8, 0.9, 0.9, 1.0,
-            )))
-            .set(properties::Property::Color(ColorF::new(0.2, 0.2, 0.2, 1.0)))
-            .set(properties::Property::HoverBgColor(ColorF::new(
-                0.6, 0.7, 0.7, 1.0,
-            )));
+            .set_default(properties::Property::TextAlign(properties::Align::Middle))
+            .set_default(properties::Property::VerticalAlign(
+                properties::VerticalAlign::Center,
+            ));
         Button {
             ext_id: 0,
             value: s.chars().collect(),
@@ -41,6 +41,7 @@ impl Button {
             event_handlers: EventHandlers::new(),
             drawn: 0,
             hovering: false,
+            click: ClickState::default(),
             enabled: true,
         }
     }
@@ -55,10 +56,25 @@ impl Button {
         self.value.clone().iter().collect()
     }
 
-    fn get_width_sums(&mut self) -> (f32, f32) {
-        let left = self.props.get_left();
-        let right = self.props.get_right();
-        let width = self.props.get_width();
+    //states stylesheet selectors can match
+    fn states(&self) -> Vec<style::State> {
+        let mut states = vec![];
+        if self.hovering && self.enabled {
+            states.push(style::State::Hover);
+        }
+        if self.click.is_pressed() && self.enabled {
+            states.push(style::State::Active);
+        }
+        if !self.enabled {
+            states.push(style::State::Disabled);
+        }
+        states
+    }
+
+    fn get_width_sums(&mut self, props: &properties::Properties) -> (f32, f32) {
+        let left = props.get_left();
+        let right = props.get_right();
+        let width = props.get_width();
 
         let mut stretchy: f32 = 0.0;
         let mut pixel: f32 = 0.0;
@@ -84,16 +100,16 @@ impl Button {
         (pixel, stretchy)
     }
 
-    fn get_height_sums(&mut self) -> (f32, f32) {
-        let top = self.props.get_top();
-        let bottom = self.props.get_bottom();
+    fn get_height_sums(&mut self, props: &properties::Properties) -> (f32, f32) {
+        let top = props.get_top();
+        let bottom = props.get_bottom();
 
         let mut stretchy: f32 = 0.0;
         let num_lines = {
             let tmp: String = self.value.iter().collect();
             tmp.lines().count() as i32
         };
-        let mut pixel: f32 = (self.props.get_size() * num_lines) as f32;
+        let mut pixel: f32 = (props.get_size() * num_lines) as f32;
 
         match top {
             properties::Unit::Stretch(_s) => stretchy += _s,
@@ -116,6 +132,10 @@ impl Element for Button {
         self.ext_id
     }
 
+    fn get_kind(&self) -> &'static str {
+        "Button"
+    }
+
     fn set(&mut self, prop: properties::Property) {
         self.props.set(prop);
     }
@@ -140,24 +160,31 @@ impl Element for Button {
         let _id = gen.get();
         self.ext_id = _id;
 
-        let mut color = self.props.get_color();
-        let mut bgcolor = self.props.get_bg_color();
-        let width = self.props.get_width();
-        let height = self.props.get_height();
-        let size = self.props.get_size() as f32;
-        let family = self.props.get_family();
-        let text_align = self.props.get_text_align();
-        let top = self.props.get_top();
-        let right = self.props.get_right();
-        let bottom = self.props.get_bottom();
-        let left = self.props.get_left();
+        let props = style::computed("Button", &self.props, &_props, self.states())
+            .resolved(extent.w, extent.h);
+        let mut color = props.get_color();
+        let mut bgcolor = props.get_bg_color();
+        let width = props.get_width();
+        let height = props.get_height();
+        let size = props.get_size() as f32;
+        let family = props.get_family();
+        let text_align = props.get_text_align();
+        let top = props.get_top();
+        let right = props.get_right();
+        let bottom = props.get_bottom();
+        let left = props.get_left();
 
         if self.hovering && self.enabled {
-            color = self.props.get_hover_color();
-            bgcolor = self.props.get_hover_bg_color();
+            color = props.get_hover_color();
+            bgcolor = props.get_hover_bg_color();
+        }
+
+        if self.click.is_pressed() && self.enabled {
+            color = props.get_active_color();
+            bgcolor = props.get_active_bg_color();
         }
 
-        let (wp_sum, ws_sum) = self.get_width_sums();
+        let (wp_sum, ws_sum) = self.get_width_sums(&props);
         let mut remaining_width = extent.w - wp_sum;
         if remaining_width < 0.0 {
             remaining_width = 0.0;
@@ -167,7 +194,7 @@ impl Element for Button {
             w_stretchy_factor = 0.0;
         }
 
-        let (hp_sum, hs_sum) = self.get_height_sums();
+        let (hp_sum, hs_sum) = self.get_height_sums(&props);
         let mut remaining_height = extent.h - hp_sum;
         if remaining_height < 0.0 {
             remaining_height = 0.0;
@@ -215,7 +242,6 @@ impl Element for Button {
             _ => (),
         }
 
-        let text_y = calc_y + (calc_h - self.text_bounds.h) / 2.0;
         let metrics = font_store.get_font_metrics(&family);
         let baseline = match metrics {
             Some(metrics) => {
@@ -225,6 +251,13 @@ impl Element for Button {
             }
             None => size,
         };
+        let text_y = text_top(
+            &props.get_vertical_align(),
+            calc_y,
+            calc_h,
+            self.text_bounds.h,
+            baseline,
+        );
 
         let mut paras = font::Paragraphs::from_chars(&self.value);
         paras.shape(
@@ -240,22 +273,20 @@ impl Element for Button {
 
         self.text_bounds = paras.get_extent();
 
-        let mut calc_w = self.text_bounds.w;
-        let mut calc_h = self.text_bounds.h;
-
-        calc_w = match width {
-            properties::Unit::Extent => extent.w,
-            properties::Unit::Pixel(px) => px,
-            properties::Unit::Stretch(s) => s * extent.w,
-            properties::Unit::Natural => calc_w,
-        };
-
-        calc_h = match height {
-            properties::Unit::Extent => extent.h,
-            properties::Unit::Pixel(px) => px,
-            properties::Unit::Stretch(s) => s * extent.h,
-            properties::Unit::Natural => calc_h,
-        };
+        let calc_w = element_size(
+            &width,
+            &props.get_min_width(),
+            &props.get_max_width(),
+            extent.w,
+            self.text_bounds.w,
+        );
+        let calc_h = element_size(
+            &height,
+            &props.get_min_height(),
+            &props.get_max_height(),
+            extent.h,
+            self.text_bounds.h,
+        );
 
         self.bounds = properties::Extent {
             x: extent.x,
@@ -291,15 +322,14 @@ impl Element for Button {
         let mut handled = false;
 
         match e {
-            PrimitiveEvent::Button(_p, b, s, m) => {
+            PrimitiveEvent::Button(_, ref b, ref s, ref m) => {
                 self.drawn = 0;
-                if ext_ids.len() == 1
-                    && ext_ids[0].0 == self.ext_id
-                    && b == properties::Button::Left
-                    && s == properties::ButtonState::Released
-                    && self.enabled
-                {
-                    handled = self.exec_handler(ElementEvent::Clicked, &m);
+                let over = ext_ids.len() == 1 && ext_ids[0].0 == self.ext_id && self.enabled;
+                //only a press that started on the button clicks it
+                if self.click.update(self.ext_id, over, &e) {
+                    handled = self.exec_handler(ElementEvent::Clicked, m);
+                } else if *b == properties::Button::Left && *s == properties::ButtonState::Pressed {
+                    handled = self.click.is_pressed();
                 }
             }
             PrimitiveEvent::HoverBegin(n_tags) => {
@@ -312,6 +342,8 @@ impl Element for Button {
                 let matched = o_tags.iter().find(|x| x.0 == self.ext_id);
                 if matched.is_some() {
                     self.hovering = false;
+                    //dragging off the button cancels the click
+                    self.click.cancel();
                 }
             }
             _ => (),
@@ -344,6 +376,9 @@ impl Element for Button {
 impl CanDisable for Button {
     fn set_enabled(&mut self, value: bool) {
         self.enabled = value;
+        if !value {
+            self.click.cancel();
+        }
     }
 
     fn get_enabled(&self) -> bool {
@@ -0,0 +1,1204 @@
+use std::any::Any;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use glutin::VirtualKeyCode;
+
+use webrender::api::*;
+
+use crate::elements::element::*;
+use crate::elements::history::{Edit, EditKind, History};
+use crate::elements::layout::*;
+use crate::elements::syntax::{Span, TokenKind, Tokenizer};
+use crate::gui::clipboard;
+use crate::gui::font;
+use crate::gui::properties;
+use crate::gui::properties::Position;
+use crate::gui::style;
+use crate::gui::window;
+
+//edits that can be undone
+const HISTORY_DEPTH: usize = 1000;
+
+//how long the caret shows and then hides for
+const BLINK_RATE: Duration = Duration::from_millis(530);
+
+//columns between tab stops unless set_tab_size says otherwise
+const TAB_SIZE: usize = 4;
+
+//lines either side of a bracket its pair is looked for in
+const BRACKET_LINES: usize = 1000;
+
+//the caret and revision a bracket pair was looked for at, and the pair
+type BracketCache = (usize, u64, Option<(usize, usize)>);
+
+//What a CodeEditor passes to its Changed handler before the text changes,
+//the chars from `offset` that are replaced and what replaces them. The
+//handler can call `cancel` to keep the text as it is.
+#[derive(Debug)]
+pub struct TextEdit {
+    pub offset: usize,
+    pub removed: String,
+    pub inserted: String,
+    cancelled: Cell<bool>,
+}
+
+impl TextEdit {
+    pub fn cancel(&self) {
+        self.cancelled.set(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.get()
+    }
+}
+
+//Text edited as lines of a monospace font, with their numbers in a gutter
+//on the left. Only the lines in view are shaped, and lines are tokenized
+//again only from an edit on, so long files stay quick. Offsets are between chars of the whole text, 0 is
+//before the first one, and columns count chars from the start of a line.
+pub struct CodeEditor {
+    ext_id: u64,
+    text: Vec<char>,
+    line_starts: Vec<usize>, //the offset every line starts at
+    props: properties::Properties,
+    bounds: properties::Extent,
+    event_handlers: EventHandlers,
+    focus: bool,
+    enabled: bool,
+    editable: bool,
+    caret: usize,
+    anchor: usize,
+    preferred_column: Option<usize>, //the shown column up and down keep to
+    selecting: bool,
+    click: ClickState,
+    scroll_x: f32,
+    scroll_y: f32,
+    tab_size: usize,
+    soft_tabs: bool,
+    tokenizer: Option<Box<dyn Tokenizer>>,
+    lexed: Vec<(Vec<Span>, u32)>, //spans and end state of the lines tokenized from the top
+    revision: u64,                //counts changes to the text and how it is tokenized
+    brackets: Option<BracketCache>, //the pair found for a caret and revision
+    shaped: HashMap<Vec<char>, font::Paragraphs>, //the lines in view by what they show
+    font: (String, i32),          //what they were shaped in
+    char_w: f32,
+    line_h: f32,
+    text_x: f32, //where the first column starts when not scrolled
+    history: History,
+    blink_start: Instant,
+    caret_moved: Option<(usize, usize)>,
+}
+
+impl CodeEditor {
+    pub fn new(s: String) -> Self {
+        let mut props = properties::Properties::new();
+        props.default();
+        if cfg!(target_os = "windows") || cfg!(target_os = "macos") {
+            props.set_default(properties::Property::Family(String::from("Courier New")));
+        }
+        let text: Vec<char> = s.chars().collect();
+        CodeEditor {
+            ext_id: 0,
+            line_starts: line_starts(&text),
+            text,
+            props,
+            bounds: properties::Extent::new(),
+            event_handlers: EventHandlers::new(),
+            focus: false,
+            enabled: true,
+            editable: true,
+            caret: 0,
+            anchor: 0,
+            preferred_column: None,
+            selecting: false,
+            click: ClickState::default(),
+            scroll_x: 0.,
+            scroll_y: 0.,
+            tab_size: TAB_SIZE,
+            soft_tabs: false,
+            tokenizer: None,
+            lexed: vec![],
+            revision: 0,
+            brackets: None,
+            shaped: HashMap::new(),
+            font: (String::new(), 0),
+            char_w: 0.,
+            line_h: 0.,
+            text_x: 0.,
+            history: History::new(HISTORY_DEPTH),
+            blink_start: Instant::now(),
+            caret_moved: None,
+        }
+    }
+
+    //Replaces the text, which can be undone until clear_history. Like
+    //every other change it goes to the Changed handler first.
+    pub fn set_value(&mut self, s: String) {
+        let len = self.text.len();
+        self.edit(EditKind::Replace, 0, len, s.chars().collect());
+    }
+
+    pub fn get_value(&self) -> String {
+        self.text.iter().collect()
+    }
+
+    pub fn set_editable(&mut self, editable: bool) {
+        self.editable = editable;
+    }
+
+    pub fn get_editable(&self) -> bool {
+        self.editable
+    }
+
+    //columns from one tab stop to the next
+    pub fn set_tab_size(&mut self, size: usize) {
+        self.tab_size = size.max(1);
+    }
+
+    pub fn get_tab_size(&self) -> usize {
+        self.tab_size
+    }
+
+    //the Tab key and indenting put in spaces up to the next stop rather
+    //than a tab
+    pub fn set_soft_tabs(&mut self, soft_tabs: bool) {
+        self.soft_tabs = soft_tabs;
+    }
+
+    pub fn get_soft_tabs(&self) -> bool {
+        self.soft_tabs
+    }
+
+    //what colours the text, None draws it all in the Color property
+    pub fn set_tokenizer(&mut self, tokenizer: Option<Box<dyn Tokenizer>>) {
+        self.tokenizer = tokenizer;
+        self.lexed.clear();
+        self.revision += 1;
+    }
+
+    pub fn get_line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    //the line and column of the caret, both from 0
+    pub fn get_caret(&self) -> (usize, usize) {
+        let line = self.line_of(self.caret);
+        (line, self.caret - self.line_starts[line])
+    }
+
+    //puts the caret at `column` of `line`, or the end of the line
+    pub fn set_caret(&mut self, line: usize, column: usize) {
+        let (start, end) = self.line_range(line.min(self.line_starts.len() - 1));
+        self.move_caret((start + column).min(end), false);
+    }
+
+    //the offsets of the anchor and the caret
+    pub fn get_selection(&self) -> (usize, usize) {
+        (self.anchor, self.caret)
+    }
+
+    //selects from `anchor` to `focus` and puts the caret at `focus`
+    pub fn set_selection(&mut self, anchor: usize, focus: usize) {
+        self.anchor = anchor.min(self.text.len());
+        self.caret = focus.min(self.text.len());
+        self.preferred_column = None;
+    }
+
+    pub fn select_all(&mut self) {
+        let len = self.text.len();
+        self.set_selection(0, len);
+    }
+
+    pub fn get_selected_text(&self) -> String {
+        let (start, end) = self.selected();
+        self.text[start..end].iter().collect()
+    }
+
+    //reverts the last edit, false if there is none or it was cancelled
+    pub fn undo(&mut self) -> bool {
+        match self.history.undo() {
+            Some(edit) => {
+                if !self.apply(&edit.inserted, &edit.removed, edit.offset) {
+                    self.history.redo();
+                    return false;
+                }
+                self.set_selection(edit.before.0, edit.before.1);
+                true
+            }
+            None => false,
+        }
+    }
+
+    //makes the last undone edit again, false if there is none or it was
+    //cancelled
+    pub fn redo(&mut self) -> bool {
+        match self.history.redo() {
+            Some(edit) => {
+                if !self.apply(&edit.removed, &edit.inserted, edit.offset) {
+                    self.history.undo();
+                    return false;
+                }
+                self.set_selection(edit.after.0, edit.after.1);
+                true
+            }
+            None => false,
+        }
+    }
+
+    //forgets every edit, such as after loading a file
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    //the line the offset is on
+    fn line_of(&self, offset: usize) -> usize {
+        match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(line) => line - 1,
+        }
+    }
+
+    //the offsets a line starts and ends at, without its line break
+    fn line_range(&self, line: usize) -> (usize, usize) {
+        let start = self.line_starts[line];
+        let end = match self.line_starts.get(line + 1) {
+            Some(next) => next - 1,
+            None => self.text.len(),
+        };
+        (start, end)
+    }
+
+    //the column the offset is shown at, with tabs taken to their stops
+    fn shown_column(&self, offset: usize) -> usize {
+        let (start, end) = self.line_range(self.line_of(offset));
+        let (_, columns) = display_columns(&self.text[start..end], self.tab_size);
+        columns[offset - start]
+    }
+
+    //the offset on `line` shown nearest to `column`
+    fn offset_at_column(&self, line: usize, column: f32) -> usize {
+        let (start, end) = self.line_range(line);
+        let (_, columns) = display_columns(&self.text[start..end], self.tab_size);
+        let mut best = 0;
+        for (i, c) in columns.iter().enumerate() {
+            if (*c as f32 - column).abs() < (columns[best] as f32 - column).abs() {
+                best = i;
+            }
+        }
+        start + best
+    }
+
+    //the offset shown under a point
+    fn offset_at(&self, p: &Position) -> usize {
+        if self.line_h <= 0. || self.char_w <= 0. {
+            return self.caret;
+        }
+        let line = ((p.y - self.bounds.y + self.scroll_y) / self.line_h)
+            .floor()
+            .max(0.) as usize;
+        let line = line.min(self.line_starts.len() - 1);
+        let column = (p.x - self.text_x + self.scroll_x) / self.char_w;
+        self.offset_at_column(line, column)
+    }
+
+    //moves the caret, extending the selection or collapsing it
+    fn move_caret(&mut self, offset: usize, extend: bool) {
+        self.caret = offset.min(self.text.len());
+        self.preferred_column = None;
+        if !extend {
+            self.anchor = self.caret;
+        }
+    }
+
+    //the selected offsets, start first
+    fn selected(&self) -> (usize, usize) {
+        (self.anchor.min(self.caret), self.anchor.max(self.caret))
+    }
+
+    //Replaces the chars from `start` to `end` with `text` and puts the
+    //caret after it. Every change to the text goes through here so it
+    //can be undone.
+    fn edit(&mut self, kind: EditKind, start: usize, end: usize, text: Vec<char>) -> bool {
+        let caret = start + text.len();
+        self.edit_to(kind, start, end, text, caret)
+    }
+
+    //edit that leaves the caret at `caret`, false if it was cancelled
+    fn edit_to(
+        &mut self,
+        kind: EditKind,
+        start: usize,
+        end: usize,
+        text: Vec<char>,
+        caret: usize,
+    ) -> bool {
+        let before = self.get_selection();
+        let removed: Vec<char> = self.text[start..end].to_vec();
+        if !self.apply(&removed, &text, start) {
+            return false;
+        }
+        self.move_caret(caret, false);
+        self.history.record(Edit {
+            kind,
+            offset: start,
+            removed,
+            inserted: text,
+            before,
+            after: self.get_selection(),
+        });
+        true
+    }
+
+    //Swaps the chars `old` at `offset` for `new` unless the Changed
+    //handler cancels it. Only the lines from the edit on are looked at.
+    fn apply(&mut self, old: &[char], new: &[char], offset: usize) -> bool {
+        let offset = offset.min(self.text.len());
+        let end = (offset + old.len()).min(self.text.len());
+        if self.event_handlers.contains_key(&ElementEvent::Changed) {
+            let change = TextEdit {
+                offset,
+                removed: self.text[offset..end].iter().collect(),
+                inserted: new.iter().collect(),
+                cancelled: Cell::new(false),
+            };
+            self.exec_handler(ElementEvent::Changed, &change);
+            if change.is_cancelled() {
+                return false;
+            }
+        }
+        //lines from the one edited on are tokenized again
+        let line = self.line_of(offset);
+        self.lexed.truncate(line);
+        self.revision += 1;
+        self.text.splice(offset..end, new.iter().cloned());
+
+        //lines that started in what was removed are gone, the ones after
+        //it move by the difference and those in what was added are new
+        let later: Vec<usize> = self.line_starts[line + 1..]
+            .iter()
+            .filter(|s| **s > end)
+            .map(|s| s - (end - offset) + new.len())
+            .collect();
+        self.line_starts.truncate(line + 1);
+        self.line_starts.extend(
+            new.iter()
+                .enumerate()
+                .filter(|(_, c)| **c == '\n')
+                .map(|(i, _)| offset + i + 1),
+        );
+        self.line_starts.extend(later);
+        true
+    }
+
+    //puts `text` in place of the selection
+    fn insert(&mut self, text: Vec<char>) {
+        let (start, end) = self.selected();
+        let kind = if start < end {
+            EditKind::Replace
+        } else {
+            EditKind::Insert
+        };
+        self.edit(kind, start, end, text);
+    }
+
+    //removes the selected chars, false if there were none
+    fn delete_selection(&mut self) -> bool {
+        let (start, end) = self.selected();
+        if start == end {
+            return false;
+        }
+        self.edit(EditKind::Delete, start, end, vec![]);
+        true
+    }
+
+    //what the Tab key puts in at a column
+    fn indent_at(&self, column: usize) -> Vec<char> {
+        if self.soft_tabs {
+            vec![' '; self.tab_size - column % self.tab_size]
+        } else {
+            vec!['\t']
+        }
+    }
+
+    //A line break indented like the line it breaks, and one level more
+    //after an opening bracket. A closing bracket right after the caret
+    //goes on a line of its own under the opening one.
+    fn newline(&mut self) {
+        let (start, end) = self.selected();
+        let (line_start, _) = self.line_range(self.line_of(start));
+        let indent: Vec<char> = self.text[line_start..start]
+            .iter()
+            .take_while(|c| **c == ' ' || **c == '\t')
+            .cloned()
+            .collect();
+        let mut text = vec!['\n'];
+        text.extend(indent.iter().cloned());
+        let last = self.text[line_start..start]
+            .iter()
+            .rev()
+            .find(|c| !c.is_whitespace())
+            .cloned();
+        let mut caret = start + text.len();
+        if let Some(closing) = last.and_then(closing_bracket) {
+            let (_, columns) = display_columns(&indent, self.tab_size);
+            text.extend(self.indent_at(columns[indent.len()]));
+            caret = start + text.len();
+            if self.text.get(end) == Some(&closing) {
+                text.push('\n');
+                text.extend(indent.iter().cloned());
+            }
+        }
+        let kind = if start < end {
+            EditKind::Replace
+        } else {
+            EditKind::Insert
+        };
+        self.edit_to(kind, start, end, text, caret);
+    }
+
+    //the start of the word after the offset
+    fn next_word(&self, offset: usize) -> usize {
+        let len = self.text.len();
+        let mut i = offset;
+        if i < len {
+            let kind = CharKind::of(self.text[i]);
+            while kind != CharKind::Space && i < len && CharKind::of(self.text[i]) == kind {
+                i += 1;
+            }
+        }
+        while i < len && CharKind::of(self.text[i]) == CharKind::Space {
+            i += 1;
+        }
+        i
+    }
+
+    //the start of the word before the offset
+    fn previous_word(&self, offset: usize) -> usize {
+        let mut i = offset;
+        while i > 0 && CharKind::of(self.text[i - 1]) == CharKind::Space {
+            i -= 1;
+        }
+        if i > 0 {
+            let kind = CharKind::of(self.text[i - 1]);
+            while i > 0 && CharKind::of(self.text[i - 1]) == kind {
+                i -= 1;
+            }
+        }
+        i
+    }
+
+    //Home goes to the first char that is not indentation, or to the start
+    //of the line from there
+    fn home(&self) -> usize {
+        let (start, end) = self.line_range(self.line_of(self.caret));
+        let first = self.text[start..end]
+            .iter()
+            .position(|c| *c != ' ' && *c != '\t')
+            .map_or(end, |i| start + i);
+        if self.caret == first {
+            start
+        } else {
+            first
+        }
+    }
+
+    //the offset `lines` above or below the caret in `column`
+    fn vertical(&self, column: usize, lines: isize) -> usize {
+        let line = self.line_of(self.caret) as isize + lines;
+        let line = line.max(0).min(self.line_starts.len() as isize - 1);
+        self.offset_at_column(line as usize, column as f32)
+    }
+
+    //Tokenizes the lines up to `line` that have not been yet. Each line
+    //starts in the state the one before it ended in.
+    fn lex_to(&mut self, line: usize) {
+        let last = line.min(self.line_starts.len() - 1);
+        while self.lexed.len() <= last {
+            let i = self.lexed.len();
+            let (start, end) = self.line_range(i);
+            let state = if i == 0 { 0 } else { self.lexed[i - 1].1 };
+            let tokens = match self.tokenizer {
+                Some(ref tokenizer) => tokenizer.tokenize(&self.text[start..end], state),
+                None => return,
+            };
+            self.lexed.push(tokens);
+        }
+    }
+
+    //false for a char the tokenizer put in a string or a comment
+    fn is_code(&self, offset: usize) -> bool {
+        let line = self.line_of(offset);
+        let column = offset - self.line_starts[line];
+        match self.lexed.get(line) {
+            Some((spans, _)) => !spans.iter().any(|s| {
+                s.start <= column
+                    && column < s.end
+                    && (s.kind == TokenKind::String || s.kind == TokenKind::Comment)
+            }),
+            None => true,
+        }
+    }
+
+    //the offset of the bracket in code that pairs with the one at `at`,
+    //looked for at most BRACKET_LINES away
+    fn matching_bracket(&mut self, at: usize) -> Option<usize> {
+        let c = self.text[at];
+        let line = self.line_of(at);
+        let mut depth = 0;
+        if let Some(closing) = closing_bracket(c) {
+            let last = (line + BRACKET_LINES).min(self.line_starts.len() - 1);
+            self.lex_to(last);
+            if !self.is_code(at) {
+                return None;
+            }
+            for i in at + 1..self.line_range(last).1 {
+                let t = self.text[i];
+                if (t != c && t != closing) || !self.is_code(i) {
+                    continue;
+                }
+                if t == c {
+                    depth += 1;
+                } else if depth == 0 {
+                    return Some(i);
+                } else {
+                    depth -= 1;
+                }
+            }
+        } else if let Some(opening) = opening_bracket(c) {
+            self.lex_to(line);
+            if !self.is_code(at) {
+                return None;
+            }
+            let first = self.line_starts[line.saturating_sub(BRACKET_LINES)];
+            for i in (first..at).rev() {
+                let t = self.text[i];
+                if (t != c && t != opening) || !self.is_code(i) {
+                    continue;
+                }
+                if t == c {
+                    depth += 1;
+                } else if depth == 0 {
+                    return Some(i);
+                } else {
+                    depth -= 1;
+                }
+            }
+        }
+        None
+    }
+
+    //the bracket next to the caret and the one it matches, looked for
+    //again only once the caret or the text changes
+    fn bracket_pair(&mut self) -> Option<(usize, usize)> {
+        if let Some((caret, revision, pair)) = self.brackets {
+            if caret == self.caret && revision == self.revision {
+                return pair;
+            }
+        }
+        let mut pair = None;
+        for at in [Some(self.caret), self.caret.checked_sub(1)]
+            .iter()
+            .filter_map(|at| *at)
+        {
+            if at < self.text.len() {
+                if let Some(m) = self.matching_bracket(at) {
+                    pair = Some((at, m));
+                    break;
+                }
+            }
+        }
+        self.brackets = Some((self.caret, self.revision, pair));
+        pair
+    }
+}
+
+impl Element for CodeEditor {
+    fn get_ext_id(&self) -> u64 {
+        self.ext_id
+    }
+
+    fn get_kind(&self) -> &'static str {
+        "CodeEditor"
+    }
+
+    fn set(&mut self, prop: properties::Property) {
+        self.props.set(prop);
+    }
+
+    fn get_properties(&self) -> properties::Properties {
+        self.props.clone()
+    }
+
+    fn render(
+        &mut self,
+        _api: &RenderApi,
+        builder: &mut DisplayListBuilder,
+        extent: properties::Extent,
+        font_store: &mut font::FontStore,
+        _props: Option<Arc<properties::Properties>>,
+        gen: &mut properties::IdGenerator,
+    ) {
+        let _id = gen.get();
+        self.ext_id = _id;
+
+        let mut states = vec![];
+        if self.focus && self.editable {
+            states.push(style::State::Focus);
+        }
+        if !self.enabled {
+            states.push(style::State::Disabled);
+        }
+        let props = style::computed("CodeEditor", &self.props, &_props, states)
+            .resolved(extent.w, extent.h);
+        let size = props.get_size() as f32;
+        let family = props.get_family();
+        let mut color = props.get_color();
+        let mut bgcolor = props.get_bg_color();
+
+        if self.focus && self.editable {
+            color = props.get_focus_color();
+            bgcolor = props.get_focus_bg_color();
+        }
+
+        if !self.enabled {
+            color = props.get_disabled_color();
+            bgcolor = props.get_disabled_bg_color();
+        }
+
+        let (_f_key, fi_key) = font_store.get_font_instance(&family, size as i32);
+        let metrics = font_store.get_font_metrics(&family);
+        let baseline = match metrics {
+            Some(metrics) => {
+                let tmp = metrics.ascent - metrics.descent;
+                let tmp = size / tmp;
+                tmp * (metrics.ascent)
+            }
+            None => size,
+        };
+
+        //lines shaped in another font are shaped again, and a space gives
+        //the width of every column
+        if self.font != (family.clone(), size as i32) {
+            self.font = (family.clone(), size as i32);
+            self.shaped.clear();
+            let mut space = font::Paragraphs::from_chars(&vec![' ']);
+            space.set_wrap(false);
+            space.shape(
+                0.,
+                0.,
+                0.,
+                0.,
+                size,
+                baseline,
+                &family,
+                &properties::Align::Left,
+            );
+            self.char_w = space
+                .get_char_at_index(0)
+                .map_or(size / 2., |ch| ch.get_metric().advance.x);
+        }
+        self.line_h = size;
+        let (char_w, line_h) = (self.char_w, self.line_h);
+        let lines = self.line_starts.len();
+
+        self.bounds = properties::Extent {
+            x: extent.x,
+            y: extent.y,
+            w: element_size(
+                &props.get_width(),
+                &props.get_min_width(),
+                &props.get_max_width(),
+                extent.w,
+                extent.w,
+            ),
+            h: element_size(
+                &props.get_height(),
+                &props.get_min_height(),
+                &props.get_max_height(),
+                extent.h,
+                lines as f32 * line_h,
+            ),
+            dpi: extent.dpi,
+        };
+
+        //the gutter fits the number of the last line and a column either
+        //side of it
+        let digits = lines.to_string().len().max(2);
+        let gutter_w = (digits + 2) as f32 * char_w;
+        self.text_x = extent.x + gutter_w + char_w / 2.;
+        let view_w = (self.bounds.w - gutter_w - char_w).max(0.);
+        let view_h = self.bounds.h;
+
+        //the caret is scrolled into view when it moves
+        let caret_line = self.line_of(self.caret);
+        let caret_column = self.shown_column(self.caret);
+        let moved = (self.caret, self.text.len());
+        let caret_moved = self.caret_moved != Some(moved);
+        if caret_moved {
+            self.caret_moved = Some(moved);
+            self.blink_start = Instant::now();
+            let y = caret_line as f32 * line_h;
+            if y < self.scroll_y {
+                self.scroll_y = y;
+            } else if y + line_h > self.scroll_y + view_h {
+                self.scroll_y = y + line_h - view_h;
+            }
+            let x = caret_column as f32 * char_w;
+            if x < self.scroll_x {
+                self.scroll_x = x;
+            } else if x + char_w > self.scroll_x + view_w {
+                self.scroll_x = x + char_w - view_w;
+            }
+        }
+        let max_y = (lines as f32 * line_h - view_h).max(0.);
+        self.scroll_y = self.scroll_y.max(0.).min(max_y);
+
+        //only the lines in view are shaped
+        let first = (self.scroll_y / line_h).floor() as usize;
+        let last = (((self.scroll_y + view_h) / line_h).ceil() as usize).min(lines);
+        let shown: Vec<(usize, Vec<char>, Vec<usize>)> = (first..last)
+            .map(|line| {
+                let (start, end) = self.line_range(line);
+                let (chars, columns) = display_columns(&self.text[start..end], self.tab_size);
+                (line, chars, columns)
+            })
+            .collect();
+        let widest = shown
+            .iter()
+            .map(|s| s.1.len())
+            .max()
+            .unwrap_or(0)
+            .max(caret_column);
+        let max_x = ((widest + 1) as f32 * char_w - view_w).max(0.);
+        self.scroll_x = self.scroll_x.max(0.).min(max_x);
+
+        let clip = LayoutRect::new(
+            LayoutPoint::new(extent.x, extent.y),
+            LayoutSize::new(self.bounds.w, self.bounds.h),
+        );
+        let mut info = LayoutPrimitiveInfo::new(clip);
+        info.tag = Some((_id, 0));
+        builder.push_rect(&info, bgcolor);
+
+        let text_clip = LayoutRect::new(
+            LayoutPoint::new(extent.x + gutter_w, extent.y),
+            LayoutSize::new((self.bounds.w - gutter_w).max(0.), self.bounds.h),
+        );
+        let gutter_clip = LayoutRect::new(
+            LayoutPoint::new(extent.x, extent.y),
+            LayoutSize::new(gutter_w.min(self.bounds.w), self.bounds.h),
+        );
+        let rect = |x: f32, y: f32, w: f32, h: f32, clip: LayoutRect| {
+            LayoutPrimitiveInfo::with_clip_rect(
+                LayoutRect::new(LayoutPoint::new(x, y), LayoutSize::new(w, h)),
+                clip,
+            )
+        };
+
+        //the lines above the ones in view are tokenized once, for what
+        //they carry over
+        let tokenized = self.tokenizer.is_some() && self.enabled;
+        if tokenized {
+            self.lex_to(last.saturating_sub(1));
+        }
+        let (sel_start, sel_end) = self.selected();
+        let brackets = if self.focus {
+            self.bracket_pair()
+        } else {
+            None
+        };
+        let x0 = self.text_x - self.scroll_x;
+        let mut cache = mem::replace(&mut self.shaped, HashMap::new());
+        let mut shape = |chars: &Vec<char>, x: f32, y: f32| {
+            let mut paras = match cache.remove(chars) {
+                Some(paras) => paras,
+                None => {
+                    let mut paras = font::Paragraphs::from_chars(chars);
+                    paras.set_wrap(false);
+                    paras.shape(
+                        x,
+                        y,
+                        view_w,
+                        line_h,
+                        size,
+                        baseline,
+                        &family,
+                        &properties::Align::Left,
+                    );
+                    paras
+                }
+            };
+            paras.position(x, y, view_w, line_h, size, &properties::Align::Left);
+            paras
+        };
+        let mut shaped = HashMap::new();
+
+        for (line, chars, columns) in shown.iter() {
+            let (start, end) = self.line_range(*line);
+            let y = extent.y + *line as f32 * line_h - self.scroll_y;
+
+            if *line == caret_line && sel_start == sel_end {
+                let info = rect(extent.x + gutter_w, y, self.bounds.w, line_h, text_clip);
+                builder.push_rect(&info, props.get_line_highlight_color());
+            }
+
+            //the selection takes in the line break after the line
+            if sel_start < sel_end && sel_start <= end && sel_end > start {
+                let a = columns[sel_start.max(start) - start];
+                let b = columns[sel_end.min(end) - start];
+                let mut w = (b - a) as f32 * char_w;
+                if sel_end > end {
+                    w += char_w;
+                }
+                let info = rect(x0 + a as f32 * char_w, y, w, line_h, text_clip);
+                builder.push_rect(&info, props.get_selection_bg_color());
+            }
+
+            if let Some((a, b)) = brackets {
+                for at in [a, b].iter().filter(|at| **at >= start && **at < end) {
+                    let column = columns[at - start];
+                    let w = (columns[at - start + 1] - column) as f32 * char_w;
+                    let info = rect(x0 + column as f32 * char_w, y, w, line_h, text_clip);
+                    builder.push_rect(&info, props.get_bracket_match_color());
+                }
+            }
+
+            if !chars.is_empty() {
+                //every shown column takes the colour of the span over it
+                let mut colors = vec![color; chars.len()];
+                if let (Some((spans, _)), true) = (self.lexed.get(*line), tokenized) {
+                    for span in spans.iter() {
+                        let span_end = span.end.min(end - start);
+                        if span.start >= span_end {
+                            continue;
+                        }
+                        for c in colors[columns[span.start]..columns[span_end]].iter_mut() {
+                            *c = span.color;
+                        }
+                    }
+                }
+                let paras = shape(chars, x0, y);
+                let info = LayoutPrimitiveInfo::new(text_clip);
+                let mut run = 0;
+                for i in 1..=colors.len() {
+                    if i == colors.len() || colors[i] != colors[run] {
+                        let glyphs = paras.get_glyphs_in(run, i);
+                        builder.push_text(
+                            &info,
+                            &glyphs,
+                            fi_key,
+                            colors[run],
+                            Some(GlyphOptions::default()),
+                        );
+                        run = i;
+                    }
+                }
+                shaped.insert(chars.clone(), paras);
+            }
+        }
+
+        let info = LayoutPrimitiveInfo::new(gutter_clip);
+        builder.push_rect(&info, props.get_gutter_bg_color());
+        for (line, _, _) in shown.iter() {
+            let number: Vec<char> = (line + 1).to_string().chars().collect();
+            let x = extent.x + gutter_w - (number.len() + 1) as f32 * char_w;
+            let y = extent.y + *line as f32 * line_h - self.scroll_y;
+            let paras = shape(&number, x, y);
+            let number_color = if *line == caret_line {
+                color
+            } else {
+                props.get_gutter_color()
+            };
+            let info = LayoutPrimitiveInfo::new(gutter_clip);
+            builder.push_text(
+                &info,
+                &paras.glyphs(),
+                fi_key,
+                number_color,
+                Some(GlyphOptions::default()),
+            );
+            shaped.insert(number, paras);
+        }
+        self.shaped = shaped;
+
+        //add the caret, it blinks from when it last moved
+        if self.focus && self.enabled && self.editable {
+            let x = x0 + caret_column as f32 * char_w;
+            let y = extent.y + caret_line as f32 * line_h - self.scroll_y;
+            window::set_ime_spot(x, y + line_h);
+            let phase = self.blink_start.elapsed().as_millis() / BLINK_RATE.as_millis();
+            window::wake_at(self.blink_start + BLINK_RATE * (phase as u32 + 1));
+            if phase % 2 == 0 {
+                let info = rect(x, y, props.get_caret_width(), line_h, text_clip);
+                builder.push_rect(&info, props.get_caret_color());
+            }
+        } else {
+            self.caret_moved = None;
+        }
+    }
+
+    fn get_bounds(&self) -> properties::Extent {
+        self.bounds.clone()
+    }
+
+    fn on_primitive_event(&mut self, ext_ids: &[ItemTag], e: PrimitiveEvent) -> bool {
+        let mut handled = false;
+        //only a release after a press on the box clicks it
+        let over = !ext_ids.is_empty() && ext_ids[0].0 == self.ext_id;
+        let clicked = self.click.update(self.ext_id, over, &e);
+        match e {
+            PrimitiveEvent::KeyInput(vkc, _sc, s, m) => {
+                if self.focus && self.enabled && s == properties::ButtonState::Pressed {
+                    let (start, end) = self.selected();
+                    let collapse = !m.shift && start < end;
+                    let caret = self.caret;
+                    let len = self.text.len();
+                    //up and down keep to the column they started from
+                    let column = self
+                        .preferred_column
+                        .unwrap_or_else(|| self.shown_column(caret));
+                    let page = if self.line_h > 0. {
+                        ((self.bounds.h / self.line_h).floor() as isize).max(1)
+                    } else {
+                        1
+                    };
+                    let target = match vkc {
+                        Some(VirtualKeyCode::Left) if collapse => Some(start),
+                        Some(VirtualKeyCode::Right) if collapse => Some(end),
+                        Some(VirtualKeyCode::Left) if m.ctrl => Some(self.previous_word(caret)),
+                        Some(VirtualKeyCode::Right) if m.ctrl => Some(self.next_word(caret)),
+                        Some(VirtualKeyCode::Left) => Some(caret.saturating_sub(1)),
+                        Some(VirtualKeyCode::Right) => Some((caret + 1).min(len)),
+                        Some(VirtualKeyCode::Up) => Some(self.vertical(column, -1)),
+                        Some(VirtualKeyCode::Down) => Some(self.vertical(column, 1)),
+                        Some(VirtualKeyCode::PageUp) => Some(self.vertical(column, -page)),
+                        Some(VirtualKeyCode::PageDown) => Some(self.vertical(column, page)),
+                        Some(VirtualKeyCode::Home) if m.ctrl => Some(0),
+                        Some(VirtualKeyCode::End) if m.ctrl => Some(len),
+                        Some(VirtualKeyCode::Home) => Some(self.home()),
+                        Some(VirtualKeyCode::End) => Some(self.line_range(self.line_of(caret)).1),
+                        _ => None,
+                    };
+                    let keeps_column = match vkc {
+                        Some(VirtualKeyCode::Up)
+                        | Some(VirtualKeyCode::Down)
+                        | Some(VirtualKeyCode::PageUp)
+                        | Some(VirtualKeyCode::PageDown) => true,
+                        _ => false,
+                    };
+                    if let Some(target) = target {
+                        self.move_caret(target, m.shift);
+                        if keeps_column {
+                            self.preferred_column = Some(column);
+                        }
+                        handled = true;
+                    }
+                }
+            }
+            PrimitiveEvent::Char(c) => {
+                //a read-only editor can still select all and copy
+                if self.focus && self.enabled && c == '\u{1}' {
+                    //select all
+                    self.select_all();
+                    handled = true;
+                } else if self.focus && self.enabled && c == '\u{3}' {
+                    //copy
+                    let (start, end) = self.selected();
+                    if start < end {
+                        clipboard::set_text(self.get_selected_text());
+                    }
+                    handled = true;
+                } else if self.focus && self.enabled && self.editable {
+                    if (c == '\x08' || c == '\u{7f}') && self.delete_selection() {
+                        //backspace and delete remove the selection
+                    } else if c == '\x08' {
+                        //backspace
+                        let caret = self.caret;
+                        if caret > 0 {
+                            self.edit(EditKind::Delete, caret - 1, caret, vec![]);
+                        }
+                    } else if c == '\u{7f}' {
+                        //delete key
+                        let caret = self.caret;
+                        if caret < self.text.len() {
+                            self.edit(EditKind::Delete, caret, caret + 1, vec![]);
+                        }
+                    } else if c == '\u{18}' {
+                        //cut
+                        let (start, end) = self.selected();
+                        if start < end {
+                            clipboard::set_text(self.get_selected_text());
+                            self.delete_selection();
+                        }
+                    } else if c == '\u{16}' {
+                        //paste
+                        if let Some(text) = clipboard::get_text() {
+                            let (start, end) = self.selected();
+                            let text = text.chars().filter(|c| *c != '\r').collect();
+                            self.edit(EditKind::Paste, start, end, text);
+                        }
+                    } else if c == '\u{1a}' {
+                        //undo
+                        self.undo();
+                    } else if c == '\u{19}' {
+                        //redo
+                        self.redo();
+                    } else if c == '\r' || c == '\n' {
+                        self.newline();
+                    } else if c == '\t' {
+                        let column = self.shown_column(self.selected().0);
+                        let indent = self.indent_at(column);
+                        self.insert(indent);
+                    } else if !c.is_control() {
+                        self.insert(vec![c]);
+                    }
+                    handled = true;
+                }
+            }
+            PrimitiveEvent::SetFocus(f) => {
+                if self.enabled && self.focus != f {
+                    self.focus = f;
+                    handled = self.exec_handler(ElementEvent::FocusChange, &f);
+                }
+            }
+            PrimitiveEvent::Button(p, b, s, m) => {
+                if !ext_ids.is_empty()
+                    && ext_ids[0].0 == self.ext_id
+                    && b == properties::Button::Left
+                {
+                    if s == properties::ButtonState::Pressed {
+                        //shift+click extends from the anchor
+                        let offset = self.offset_at(&p);
+                        self.move_caret(offset, m.shift);
+                        self.selecting = true;
+                    } else if s == properties::ButtonState::Released {
+                        self.selecting = false;
+                        if clicked {
+                            handled = self.exec_handler(ElementEvent::Clicked, &m);
+                        }
+                    }
+                } else if b == properties::Button::Left && s == properties::ButtonState::Released {
+                    self.selecting = false;
+                }
+            }
+            PrimitiveEvent::CursorMoved(p) => {
+                if self.selecting {
+                    let offset = self.offset_at(&p);
+                    self.move_caret(offset, true);
+                }
+            }
+            PrimitiveEvent::Wheel(dx, dy) => {
+                if !ext_ids.is_empty() && ext_ids[0].0 == self.ext_id {
+                    self.scroll_x -= dx;
+                    self.scroll_y -= dy;
+                    handled = true;
+                }
+            }
+            _ => (),
+        }
+        handled
+    }
+
+    fn set_handler(&mut self, e: ElementEvent, f: EventFn) {
+        self.event_handlers.insert(e, f);
+    }
+
+    fn exec_handler(&mut self, e: ElementEvent, d: &dyn Any) -> bool {
+        let h = self.event_handlers.get_mut(&e).cloned();
+        if let Some(mut h) = h {
+            h.call(self, d)
+        } else {
+            false
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl CanDisable for CodeEditor {
+    fn set_enabled(&mut self, value: bool) {
+        self.enabled = value;
+    }
+
+    fn get_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+//the offset of the start of every line
+fn line_starts(text: &[char]) -> Vec<usize> {
+    let mut starts = vec![0];
+    starts.extend(
+        text.iter()
+            .enumerate()
+            .filter(|(_, c)| **c == '\n')
+            .map(|(i, _)| i + 1),
+    );
+    starts
+}
+
+//A line as it is shown, with its tabs turned into spaces up to the next
+//stop, and the column each char starts at. The last column is where the
+//line ends.
+fn display_columns(line: &[char], tab_size: usize) -> (Vec<char>, Vec<usize>) {
+    let mut chars = vec![];
+    let mut columns = vec![];
+    for c in line.iter() {
+        columns.push(chars.len());
+        if *c == '\t' {
+            let stop = (chars.len() / tab_size + 1) * tab_size;
+            chars.resize(stop, ' ');
+        } else {
+            chars.push(*c);
+        }
+    }
+    columns.push(chars.len());
+    (chars, columns)
+}
+
+fn closing_bracket(c: char) -> Option<char> {
+    match c {
+        '(' => Some(')'),
+        '[' => Some(']'),
+        '{' => Some('}'),
+        _ => None,
+    }
+}
+
+fn opening_bracket(c: char) -> Option<char> {
+    match c {
+        ')' => Some('('),
+        ']' => Some('['),
+        '}' => Some('{'),
+        _ => None,
+    }
+}
+
+//what Ctrl+arrows move over a run of
+#[derive(PartialEq)]
+enum CharKind {
+    Word,
+    Space,
+    Other,
+}
+
+impl CharKind {
+    fn of(c: char) -> CharKind {
+        if c.is_alphanumeric() || c == '_' {
+            CharKind::Word
+        } else if c.is_whitespace() {
+            CharKind::Space
+        } else {
+            CharKind::Other
+        }
+    }
+}
@@ -9,6 +9,7 @@ use glutin::{ScanCode, VirtualKeyCode};
 use webrender::api::*;
 use winit;
 
+use crate::elements::layout::min_size;
 use crate::gui::font;
 use crate::gui::properties;
 
@@ -36,6 +37,7 @@ pub enum PrimitiveEvent {
     DPI(f64),
     HoverBegin(Vec<ItemTag>),
     HoverEnd(Vec<ItemTag>),
+    Wheel(f32, f32), //how far the content under the cursor moves, in pixels
 }
 
 #[derive(Debug, Clone, Eq)]
@@ -44,6 +46,9 @@ pub enum ElementEvent {
     FocusChange,
     HoverBegin,
     HoverEnd,
+    ValidityChange, //with whether it is valid now
+    Changed,        //with the old and new value, before it changes
+    Submit,         //with the value, when Enter is pressed on a single line
 }
 
 impl Hash for ElementEvent {
@@ -96,6 +101,10 @@ pub type EventHandlers = HashMap<ElementEvent, EventFn>;
 
 pub trait Element: Send + Sync {
     fn get_ext_id(&self) -> u64;
+    //the type of element, as stylesheets and themes name it
+    fn get_kind(&self) -> &'static str {
+        "Element"
+    }
     fn set(&mut self, prop: properties::Property);
     //fn get(&self, prop: &properties::Property) -> Option<&properties::Property>;
     fn get_properties(&self) -> properties::Properties;
@@ -109,6 +118,11 @@ pub trait Element: Send + Sync {
         id: &mut properties::IdGenerator
     );
     fn get_bounds(&self) -> properties::Extent;
+    //Smallest size the element can take with its margins, which a window
+    //keeps itself from shrinking below. Containers add up their children.
+    fn get_min_size(&self) -> (f32, f32) {
+        min_size(&self.get_properties(), (0.0, 0.0))
+    }
     #[allow(unused)]
     fn on_primitive_event(&mut self, item_tag: &[ItemTag], e: PrimitiveEvent) -> bool;
     #[allow(unused)]
@@ -132,6 +146,59 @@ pub trait Element: Send + Sync {
 
 pub type ElementObj = Arc<Mutex<dyn Element>>;
 
+//In debug builds reports the properties `elm` is missing. Containers
+//call it for each child before rendering it.
+pub fn validate_element(elm: &dyn Element) {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+    let props = elm.get_properties();
+    let name = match props.try_get_id() {
+        Some(ref id) if !id.is_empty() => format!("{} #{}", elm.get_kind(), id),
+        _ => format!("{} {}", elm.get_kind(), elm.get_ext_id()),
+    };
+    props.validate(&name);
+}
+
+//Follows the left button for an element that fires Clicked. Only a
+//release over the element after a press over it is a click, and the
+//pointer leaving the element in between cancels it.
+#[derive(Clone, Debug, Default)]
+pub struct ClickState {
+    pressed: bool,
+}
+
+impl ClickState {
+    //true when `e` completes a click on the element `ext_id`. `over` is
+    //whether a button event is over the element and not taken by anything
+    //on top of it.
+    pub fn update(&mut self, ext_id: u64, over: bool, e: &PrimitiveEvent) -> bool {
+        match e {
+            PrimitiveEvent::Button(_, properties::Button::Left, state, _) => {
+                let released = *state == properties::ButtonState::Released;
+                let clicked = over && self.pressed && released;
+                self.pressed = over && !released;
+                clicked
+            }
+            PrimitiveEvent::HoverEnd(tags) => {
+                if tags.iter().any(|t| t.0 == ext_id) {
+                    self.pressed = false;
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+
+    pub fn cancel(&mut self) {
+        self.pressed = false;
+    }
+}
+
 pub trait HasChildren: Element {
     #[allow(unused)]
     fn get_child(&self, i: u32) -> Option<Arc<Mutex<dyn Element>>> {
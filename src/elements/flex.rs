@@ -0,0 +1,691 @@
+use std::any::Any;
+use std::sync::{Arc, Mutex};
+
+use webrender::api::*;
+
+use crate::elements::element::*;
+use crate::elements::layout::*;
+use crate::gui::font;
+use crate::gui::properties;
+use crate::gui::properties::{CrossAlign, Unit};
+use crate::gui::style;
+use crate::util::*;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FlexDirection {
+    Row,
+    RowReverse,
+    Column,
+    ColumnReverse,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FlexWrap {
+    NoWrap,
+    Wrap,
+    WrapReverse,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Justify {
+    Start,
+    End,
+    Center,
+    SpaceBetween,
+    SpaceAround,
+    SpaceEvenly,
+}
+
+//layout state of a single child for the current frame
+#[derive(Clone, Debug)]
+struct FlexItem {
+    basis: f32,
+    grow: f32,
+    shrink: f32,
+    min: f32,
+    max: f32,
+    main: f32,
+    frozen: bool,
+    cross: f32,
+    cross_min: f32,
+    cross_max: f32,
+    stretch: bool,
+    align: CrossAlign,
+}
+
+impl FlexItem {
+    fn clamp(&self, v: f32) -> f32 {
+        v.min(self.max).max(self.min)
+    }
+
+    fn clamp_cross(&self, v: f32) -> f32 {
+        v.min(self.cross_max).max(self.cross_min)
+    }
+}
+
+pub struct Flex {
+    ext_id: u64,
+    children: Vec<Arc<Mutex<dyn Element>>>,
+    props: properties::Properties,
+    bounds: properties::Extent,
+    handlers: EventHandlers,
+    click: ClickState,
+    direction: FlexDirection,
+    wrap: FlexWrap,
+    justify: Justify,
+    align_items: CrossAlign,
+    row_gap: f32,
+    column_gap: f32,
+}
+
+impl Flex {
+    pub fn new() -> Self {
+        let mut props = properties::Properties::new();
+        props.default();
+        Flex {
+            ext_id: 0,
+            children: Vec::new(),
+            props,
+            bounds: properties::Extent::new(),
+            handlers: EventHandlers::new(),
+            click: ClickState::default(),
+            direction: FlexDirection::Row,
+            wrap: FlexWrap::NoWrap,
+            justify: Justify::Start,
+            align_items: CrossAlign::Stretch,
+            row_gap: 0.0,
+            column_gap: 0.0,
+        }
+    }
+
+    pub fn set_direction(&mut self, direction: FlexDirection) {
+        self.direction = direction;
+    }
+
+    pub fn get_direction(&self) -> FlexDirection {
+        self.direction.clone()
+    }
+
+    pub fn set_wrap(&mut self, wrap: FlexWrap) {
+        self.wrap = wrap;
+    }
+
+    pub fn get_wrap(&self) -> FlexWrap {
+        self.wrap.clone()
+    }
+
+    pub fn set_justify_content(&mut self, justify: Justify) {
+        self.justify = justify;
+    }
+
+    pub fn get_justify_content(&self) -> Justify {
+        self.justify.clone()
+    }
+
+    //CrossAlign::Auto is treated as Stretch for the container
+    pub fn set_align_items(&mut self, align: CrossAlign) {
+        self.align_items = align;
+    }
+
+    pub fn get_align_items(&self) -> CrossAlign {
+        self.align_items.clone()
+    }
+
+    pub fn set_gap(&mut self, gap: f32) {
+        self.row_gap = gap;
+        self.column_gap = gap;
+    }
+
+    //space between lines of a row flex, or between items of a column flex
+    pub fn set_row_gap(&mut self, gap: f32) {
+        self.row_gap = gap;
+    }
+
+    //space between items of a row flex, or between lines of a column flex
+    pub fn set_column_gap(&mut self, gap: f32) {
+        self.column_gap = gap;
+    }
+
+    fn is_row(&self) -> bool {
+        self.direction == FlexDirection::Row || self.direction == FlexDirection::RowReverse
+    }
+
+    fn is_reverse(&self) -> bool {
+        self.direction == FlexDirection::RowReverse
+            || self.direction == FlexDirection::ColumnReverse
+    }
+
+    fn collect_items(
+        &self,
+        main_size: Option<f32>,
+        cross_size: Option<f32>,
+        computed: &Arc<properties::Properties>,
+    ) -> Vec<FlexItem> {
+        let row = self.is_row();
+        let main_parent = main_size.unwrap_or(0.0);
+        let cross_parent = cross_size.unwrap_or(0.0);
+        let mut items = vec![];
+
+        for elm in self.children.iter() {
+            match elm.lock() {
+                Ok(ref _e) => {
+                    let (parent_w, parent_h) = if row {
+                        (main_parent, cross_parent)
+                    } else {
+                        (cross_parent, main_parent)
+                    };
+                    let props = inherited(&**_e, computed).resolved(parent_w, parent_h);
+                    let b = _e.get_bounds();
+                    let (main_unit, cross_unit, natural_main, natural_cross) = if row {
+                        (props.get_width(), props.get_height(), sane(b.w), sane(b.h))
+                    } else {
+                        (props.get_height(), props.get_width(), sane(b.h), sane(b.w))
+                    };
+                    let (min_unit, max_unit, cross_min_unit, cross_max_unit) = if row {
+                        (
+                            props.get_min_width(),
+                            props.get_max_width(),
+                            props.get_min_height(),
+                            props.get_max_height(),
+                        )
+                    } else {
+                        (
+                            props.get_min_height(),
+                            props.get_max_height(),
+                            props.get_min_width(),
+                            props.get_max_width(),
+                        )
+                    };
+
+                    let mut grow = props.get_flex_grow();
+                    let shrink = props.get_flex_shrink();
+
+                    //a stretchy main size works like a zero basis that grows
+                    let basis_unit = props
+                        .get_flex_basis()
+                        .resolve(main_parent, props.get_size() as f32);
+                    let basis = match basis_unit {
+                        Unit::Pixel(_p) => _p,
+                        Unit::Extent => main_parent,
+                        Unit::Stretch(_s) => {
+                            if grow == 0.0 {
+                                grow = _s;
+                            }
+                            0.0
+                        }
+                        _ => match main_unit {
+                            Unit::Pixel(_p) => _p,
+                            Unit::Extent => main_parent,
+                            Unit::Stretch(_s) => {
+                                if grow == 0.0 {
+                                    grow = _s;
+                                }
+                                0.0
+                            }
+                            _ => natural_main,
+                        },
+                    };
+
+                    let mut align = props.get_align_self();
+                    if align == CrossAlign::Auto {
+                        align = self.align_items.clone();
+                    }
+                    if align == CrossAlign::Auto {
+                        align = CrossAlign::Stretch;
+                    }
+
+                    //only elements without a fixed cross size are stretched
+                    let stretch = align == CrossAlign::Stretch && cross_unit == Unit::Stretch(0.0);
+                    let cross = match cross_unit {
+                        Unit::Pixel(_p) => _p,
+                        Unit::Extent => cross_parent,
+                        _ => natural_cross,
+                    };
+
+                    let min = resolve_bound(&min_unit, main_parent, natural_main, 0.0);
+                    let max =
+                        resolve_bound(&max_unit, main_parent, natural_main, std::f32::INFINITY);
+                    let cross_min =
+                        resolve_bound(&cross_min_unit, cross_parent, natural_cross, 0.0);
+                    let cross_max = resolve_bound(
+                        &cross_max_unit,
+                        cross_parent,
+                        natural_cross,
+                        std::f32::INFINITY,
+                    );
+
+                    items.push(FlexItem {
+                        basis,
+                        grow,
+                        shrink,
+                        min,
+                        max: max.max(min),
+                        main: basis,
+                        frozen: false,
+                        cross,
+                        cross_min,
+                        cross_max: cross_max.max(cross_min),
+                        stretch,
+                        align,
+                    });
+                }
+                Err(_err_str) => panic!("unable to lock element : {}", _err_str),
+            }
+        }
+
+        items
+    }
+
+    //splits the items in to lines that fit the available main size
+    fn break_lines(&self, items: &[FlexItem], main_size: Option<f32>, gap: f32) -> Vec<Vec<usize>> {
+        let mut lines = vec![];
+        let mut line: Vec<usize> = vec![];
+        let mut used = 0.0;
+
+        for (i, item) in items.iter().enumerate() {
+            let hyp = item.clamp(item.basis);
+            if let Some(avail) = main_size {
+                let next = if line.is_empty() {
+                    hyp
+                } else {
+                    used + gap + hyp
+                };
+                if self.wrap != FlexWrap::NoWrap && !line.is_empty() && next > avail {
+                    lines.push(line);
+                    line = vec![];
+                    used = hyp;
+                    line.push(i);
+                    continue;
+                }
+                used = next;
+            }
+            line.push(i);
+        }
+        if !line.is_empty() {
+            lines.push(line);
+        }
+
+        lines
+    }
+
+    //grows or shrinks the items of a line, freezing any item that hits
+    //its min or max so the rest of the space goes to the others
+    fn resolve_line(items: &mut [FlexItem], line: &[usize], avail: f32, gap: f32) {
+        let gaps = gap * (line.len() as f32 - 1.0).max(0.0);
+        let hyp_sum: f32 = line.iter().map(|i| items[*i].clamp(items[*i].basis)).sum();
+        let growing = hyp_sum + gaps < avail;
+
+        for i in line.iter() {
+            let item = &mut items[*i];
+            let hyp = item.clamp(item.basis);
+            item.frozen = (growing && (item.grow == 0.0 || item.basis > hyp))
+                || (!growing && (item.shrink == 0.0 || item.basis < hyp));
+            item.main = hyp;
+        }
+
+        loop {
+            let mut used = gaps;
+            let mut grow_sum = 0.0;
+            let mut shrink_sum = 0.0;
+            for i in line.iter() {
+                let item = &items[*i];
+                if item.frozen {
+                    used += item.main;
+                } else {
+                    used += item.basis;
+                    grow_sum += item.grow;
+                    shrink_sum += item.shrink * item.basis;
+                }
+            }
+            if line.iter().all(|i| items[*i].frozen) {
+                break;
+            }
+
+            let free = avail - used;
+            let mut violation = 0.0;
+            for i in line.iter() {
+                let item = &mut items[*i];
+                if item.frozen {
+                    continue;
+                }
+                let target = if growing {
+                    if grow_sum > 0.0 {
+                        item.basis + free * item.grow / grow_sum
+                    } else {
+                        item.basis
+                    }
+                } else if shrink_sum > 0.0 {
+                    item.basis + free * item.shrink * item.basis / shrink_sum
+                } else {
+                    item.basis
+                };
+                item.main = item.clamp(target);
+                violation += item.main - target;
+            }
+
+            for i in line.iter() {
+                let item = &mut items[*i];
+                if item.frozen {
+                    continue;
+                }
+                let target_was_clamped_up = item.main <= item.min;
+                let target_was_clamped_down = item.main >= item.max;
+                if violation == 0.0
+                    || (violation > 0.0 && target_was_clamped_up)
+                    || (violation < 0.0 && target_was_clamped_down)
+                {
+                    item.frozen = true;
+                }
+            }
+        }
+    }
+}
+
+impl Default for Flex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Element for Flex {
+    fn get_ext_id(&self) -> u64 {
+        self.ext_id
+    }
+
+    fn get_kind(&self) -> &'static str {
+        "Flex"
+    }
+
+    fn set(&mut self, prop: properties::Property) {
+        self.props.set(prop);
+    }
+
+    fn get_properties(&self) -> properties::Properties {
+        self.props.clone()
+    }
+
+    fn render(
+        &mut self,
+        api: &RenderApi,
+        builder: &mut DisplayListBuilder,
+        extent: properties::Extent,
+        font_store: &mut font::FontStore,
+        _props: Option<Arc<properties::Properties>>,
+        gen: &mut properties::IdGenerator,
+    ) {
+        //the values children inherit from
+        let computed = Arc::new(style::computed("Flex", &self.props, &_props, vec![]));
+        let bgcolor = computed.get_bg_color();
+        let row = self.is_row();
+        let props = computed.resolved(extent.w, extent.h);
+
+        let width = container_size(
+            props.get_width(),
+            extent.w,
+            &props.get_min_width(),
+            &props.get_max_width(),
+        );
+        let height = container_size(
+            props.get_height(),
+            extent.h,
+            &props.get_min_height(),
+            &props.get_max_height(),
+        );
+        let (main_size, cross_size) = if row {
+            (width, height)
+        } else {
+            (height, width)
+        };
+        let (main_gap, cross_gap) = if row {
+            (self.column_gap, self.row_gap)
+        } else {
+            (self.row_gap, self.column_gap)
+        };
+
+        let mut items = self.collect_items(main_size, cross_size, &computed);
+        let lines = self.break_lines(&items, main_size, main_gap);
+
+        let mut content_main: f32 = 0.0;
+        let mut line_crosses = vec![];
+        for line in lines.iter() {
+            let gaps = main_gap * (line.len() as f32 - 1.0).max(0.0);
+            let avail = match main_size {
+                Some(_m) => _m,
+                None => {
+                    line.iter()
+                        .map(|i| items[*i].clamp(items[*i].basis))
+                        .sum::<f32>()
+                        + gaps
+                }
+            };
+            Flex::resolve_line(&mut items, line, avail, main_gap);
+
+            let used: f32 = line.iter().map(|i| items[*i].main).sum::<f32>() + gaps;
+            content_main = content_main.max(used);
+
+            let line_cross = match cross_size {
+                Some(_c) if lines.len() == 1 => _c,
+                _ => line
+                    .iter()
+                    .map(|i| {
+                        let item = &items[*i];
+                        if item.stretch {
+                            item.cross_min
+                        } else {
+                            item.clamp_cross(item.cross)
+                        }
+                    })
+                    .fold(0.0, f32::max),
+            };
+            line_crosses.push(line_cross);
+        }
+
+        let content_cross = line_crosses.iter().sum::<f32>()
+            + cross_gap * (line_crosses.len() as f32 - 1.0).max(0.0);
+        let main_size = main_size.unwrap_or(content_main);
+        let cross_size = cross_size.unwrap_or(content_cross);
+
+        let (w, h) = if row {
+            (main_size, cross_size)
+        } else {
+            (cross_size, main_size)
+        };
+        self.bounds = properties::Extent {
+            x: extent.x,
+            y: extent.y,
+            w,
+            h,
+            dpi: extent.dpi,
+        };
+
+        let _id = gen.get();
+        self.ext_id = _id;
+
+        let mut info = LayoutPrimitiveInfo::new((extent.x, extent.y).by(w, h));
+        info.tag = Some((_id, 0));
+        builder.push_rect(&info, bgcolor);
+
+        let reverse = self.is_reverse();
+        let mut line_pos = 0.0;
+        for (l, line) in lines.iter().enumerate() {
+            let line_cross = line_crosses[l];
+            let cross_pos = if self.wrap == FlexWrap::WrapReverse {
+                cross_size - line_pos - line_cross
+            } else {
+                line_pos
+            };
+
+            let gaps = main_gap * (line.len() as f32 - 1.0).max(0.0);
+            let used: f32 = line.iter().map(|i| items[*i].main).sum::<f32>() + gaps;
+            let (offset, extra) = justify_offsets(&self.justify, main_size - used, line.len());
+
+            let mut main_pos = offset;
+            for i in line.iter() {
+                let item = &items[*i];
+                let item_cross = if item.stretch {
+                    item.clamp_cross(line_cross)
+                } else {
+                    item.clamp_cross(item.cross)
+                };
+                let item_offset = match item.align {
+                    CrossAlign::Center => (line_cross - item_cross) / 2.0,
+                    CrossAlign::End => line_cross - item_cross,
+                    _ => 0.0,
+                };
+
+                let main_start = if reverse {
+                    main_size - main_pos - item.main
+                } else {
+                    main_pos
+                };
+                let child_extent = if row {
+                    properties::Extent {
+                        x: extent.x + main_start,
+                        y: extent.y + cross_pos + item_offset,
+                        w: item.main,
+                        h: item_cross,
+                        dpi: extent.dpi,
+                    }
+                } else {
+                    properties::Extent {
+                        x: extent.x + cross_pos + item_offset,
+                        y: extent.y + main_start,
+                        w: item_cross,
+                        h: item.main,
+                        dpi: extent.dpi,
+                    }
+                };
+
+                match self.children[*i].lock() {
+                    Ok(ref mut elm) => {
+                        let props = elm.get_properties();
+                        let child_extent = properties::Extent {
+                            w: given_size(&props.get_width(), child_extent.w, w),
+                            h: given_size(&props.get_height(), child_extent.h, h),
+                            ..child_extent
+                        };
+                        validate_element(&**elm);
+                        elm.render(
+                            api,
+                            builder,
+                            child_extent,
+                            font_store,
+                            Some(computed.clone()),
+                            gen,
+                        );
+                    }
+                    Err(_err_str) => panic!("unable to lock element : {}", _err_str),
+                }
+
+                main_pos += item.main + main_gap + extra;
+            }
+
+            line_pos += line_cross + cross_gap;
+        }
+    }
+
+    fn get_bounds(&self) -> properties::Extent {
+        self.bounds.clone()
+    }
+
+    //lines that wrap need only fit the largest child
+    fn get_min_size(&self) -> (f32, f32) {
+        if self.wrap != FlexWrap::NoWrap {
+            return min_size(&self.props, children_min_size(&self.children, false, false));
+        }
+        let row = self.is_row();
+        let (w, h) = children_min_size(&self.children, row, !row);
+        let gaps = self.children.len().saturating_sub(1) as f32;
+        let content = if row {
+            (w + gaps * self.column_gap, h)
+        } else {
+            (w, h + gaps * self.row_gap)
+        };
+        min_size(&self.props, content)
+    }
+
+    fn on_primitive_event(&mut self, ext_ids: &[ItemTag], e: PrimitiveEvent) -> bool {
+        let mut handled = false;
+        for _child_elm in self.children.iter_mut() {
+            match (&e, _child_elm.lock()) {
+                (PrimitiveEvent::SetFocus(_), Ok(ref mut _child_elm)) => {
+                    if ext_ids.len() > 1
+                        && ext_ids[0].0 == self.ext_id
+                        && ext_ids[1].0 == _child_elm.get_ext_id()
+                    {
+                        _child_elm
+                            .on_primitive_event(&ext_ids[1..], PrimitiveEvent::SetFocus(true));
+                    } else {
+                        _child_elm.on_primitive_event(&[], PrimitiveEvent::SetFocus(false));
+                    }
+                }
+                (PrimitiveEvent::Char(_), Ok(ref mut _child_elm))
+                | (PrimitiveEvent::KeyInput(..), Ok(ref mut _child_elm)) => {
+                    handled = _child_elm.on_primitive_event(&[], e.clone());
+                    if handled {
+                        break;
+                    }
+                }
+                (PrimitiveEvent::HoverBegin(_), Ok(ref mut _child_elm)) => {
+                    _child_elm.on_primitive_event(&[], e.clone());
+                }
+                (PrimitiveEvent::HoverEnd(_), Ok(ref mut _child_elm)) => {
+                    _child_elm.on_primitive_event(&[], e.clone());
+                }
+                (_, Ok(ref mut _child_elm)) => {
+                    if !handled {
+                        if ext_ids.len() == 1 {
+                            handled = _child_elm.on_primitive_event(&[], e.clone());
+                        } else if ext_ids.len() > 1 {
+                            handled = _child_elm.on_primitive_event(&ext_ids[1..], e.clone());
+                        }
+                    }
+                }
+                (_, Err(_err_str)) => {
+                    //this should be unreachable
+                    panic!("unable to lock element : {}", _err_str)
+                }
+            }
+        }
+        // if none of the children handled the event
+        // see if you can handle it here
+        let over = !handled && !ext_ids.is_empty() && ext_ids[0].0 == self.ext_id;
+        if self.click.update(self.ext_id, over, &e) {
+            if let PrimitiveEvent::Button(_p, _b, _s, m) = e {
+                handled = self.exec_handler(ElementEvent::Clicked, &m);
+            }
+        }
+        handled
+    }
+
+    fn set_handler(&mut self, _e: ElementEvent, _f: EventFn) {
+        self.handlers.insert(_e, _f);
+    }
+
+    fn exec_handler(&mut self, _e: ElementEvent, _d: &dyn Any) -> bool {
+        let h = self.handlers.get_mut(&_e).cloned();
+        if let Some(mut h) = h {
+            h.call(self, _d)
+        } else {
+            false
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl HasChildren for Flex {
+    fn get_child(&self, i: u32) -> Option<Arc<Mutex<dyn Element>>> {
+        self.children.get(i as usize).cloned()
+    }
+
+    fn append(&mut self, e: Arc<Mutex<dyn Element>>) -> Option<Arc<Mutex<dyn Element>>> {
+        self.children.push(e);
+        None
+    }
+}
@@ -0,0 +1,679 @@
+use std::any::Any;
+use std::sync::{Arc, Mutex};
+
+use webrender::api::*;
+
+use crate::elements::element::*;
+use crate::elements::layout::*;
+use crate::gui::font;
+use crate::gui::properties;
+use crate::gui::properties::{CrossAlign, Unit};
+use crate::gui::style;
+use crate::util::*;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Track {
+    Pixel(f32),
+    //largest natural size of the elements in the track
+    Natural,
+    //share of the space left after all other tracks, like css `fr`
+    Stretch(f32),
+    //natural size, also takes up left over space if there are no Stretch tracks
+    Auto,
+    //between the first and second size
+    MinMax(Box<Track>, Box<Track>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GridCell {
+    pub row: usize,
+    pub column: usize,
+    pub row_span: usize,
+    pub column_span: usize,
+    //horizontal alignment in the cell
+    pub justify: CrossAlign,
+    //vertical alignment in the cell
+    pub align: CrossAlign,
+}
+
+impl GridCell {
+    pub fn new(row: usize, column: usize) -> GridCell {
+        GridCell {
+            row,
+            column,
+            row_span: 1,
+            column_span: 1,
+            justify: CrossAlign::Auto,
+            align: CrossAlign::Auto,
+        }
+    }
+
+    pub fn span(mut self, rows: usize, columns: usize) -> GridCell {
+        self.row_span = rows.max(1);
+        self.column_span = columns.max(1);
+        self
+    }
+
+    pub fn alignment(mut self, justify: CrossAlign, align: CrossAlign) -> GridCell {
+        self.justify = justify;
+        self.align = align;
+        self
+    }
+}
+
+//sizing state of a single row or column for the current frame
+#[derive(Clone, Debug)]
+struct TrackSize {
+    base: f32,
+    limit: f32,
+    flex: f32,
+    auto: bool,
+}
+
+impl TrackSize {
+    fn from_track(track: &Track, natural: f32) -> TrackSize {
+        match track {
+            Track::Pixel(_p) => TrackSize {
+                base: *_p,
+                limit: *_p,
+                flex: 0.0,
+                auto: false,
+            },
+            Track::Natural => TrackSize {
+                base: natural,
+                limit: natural,
+                flex: 0.0,
+                auto: false,
+            },
+            Track::Auto => TrackSize {
+                base: natural,
+                limit: natural,
+                flex: 0.0,
+                auto: true,
+            },
+            Track::Stretch(_s) => TrackSize {
+                base: 0.0,
+                limit: 0.0,
+                flex: *_s,
+                auto: false,
+            },
+            Track::MinMax(min, max) => {
+                let min = TrackSize::from_track(min, natural);
+                let max = TrackSize::from_track(max, natural);
+                TrackSize {
+                    base: min.base,
+                    limit: max.limit.max(min.base),
+                    flex: max.flex,
+                    auto: max.auto,
+                }
+            }
+        }
+    }
+}
+
+//sizes the tracks of one axis. `spans` is the (start, span, natural size)
+//of every element placed on that axis.
+fn size_tracks(
+    tracks: &[Track],
+    spans: &[(usize, usize, f32)],
+    available: Option<f32>,
+    gap: f32,
+) -> Vec<f32> {
+    let mut naturals = vec![0.0f32; tracks.len()];
+    for (start, span, natural) in spans.iter() {
+        if *span == 1 {
+            naturals[*start] = naturals[*start].max(*natural);
+        }
+    }
+
+    let mut sizes: Vec<TrackSize> = tracks
+        .iter()
+        .zip(naturals.iter())
+        .map(|(t, n)| TrackSize::from_track(t, *n))
+        .collect();
+
+    //elements spanning several tracks share their extra size between
+    //the spanned tracks that are not fixed
+    for (start, span, natural) in spans.iter() {
+        if *span < 2 {
+            continue;
+        }
+        let end = (*start + *span).min(sizes.len());
+        let covered: f32 =
+            sizes[*start..end].iter().map(|t| t.base).sum::<f32>() + gap * (*span as f32 - 1.0);
+        if covered >= *natural {
+            continue;
+        }
+        let growable: Vec<usize> = (*start..end)
+            .filter(|i| match tracks[*i] {
+                Track::Pixel(_) => false,
+                _ => true,
+            })
+            .collect();
+        if growable.is_empty() {
+            continue;
+        }
+        let share = (*natural - covered) / growable.len() as f32;
+        for i in growable {
+            sizes[i].base += share;
+            sizes[i].limit = sizes[i].limit.max(sizes[i].base);
+        }
+    }
+
+    let gaps = gap * (sizes.len() as f32 - 1.0).max(0.0);
+    let available = match available {
+        Some(_a) => _a,
+        //without a definite size flexible tracks are their natural size
+        None => {
+            return sizes
+                .iter()
+                .zip(naturals.iter())
+                .map(|(t, n)| if t.flex > 0.0 { t.base.max(*n) } else { t.base })
+                .collect();
+        }
+    };
+
+    //grow tracks towards their limit
+    let mut free = available - gaps - sizes.iter().map(|t| t.base).sum::<f32>();
+    loop {
+        let growing: Vec<usize> = (0..sizes.len())
+            .filter(|i| sizes[*i].limit > sizes[*i].base)
+            .collect();
+        if free <= 0.0 || growing.is_empty() {
+            break;
+        }
+        let share = free / growing.len() as f32;
+        let mut capped = false;
+        for i in growing {
+            let add = share.min(sizes[i].limit - sizes[i].base);
+            capped = capped || add < share;
+            sizes[i].base += add;
+            free -= add;
+        }
+        if !capped {
+            free = 0.0;
+            break;
+        }
+    }
+
+    //split what is left between the stretchy tracks. Tracks whose base
+    //is larger than their share keep their base.
+    let mut flexible: Vec<usize> = (0..sizes.len()).filter(|i| sizes[*i].flex > 0.0).collect();
+    if !flexible.is_empty() {
+        loop {
+            let fixed: f32 = (0..sizes.len())
+                .filter(|i| !flexible.contains(i))
+                .map(|i| sizes[i].base)
+                .sum();
+            let flex_sum: f32 = flexible.iter().map(|i| sizes[*i].flex).sum();
+            let unit = ((available - gaps - fixed) / flex_sum).max(0.0);
+            let too_small: Vec<usize> = flexible
+                .iter()
+                .cloned()
+                .filter(|i| sizes[*i].base > unit * sizes[*i].flex)
+                .collect();
+            if too_small.is_empty() {
+                for i in flexible.iter() {
+                    sizes[*i].base = unit * sizes[*i].flex;
+                }
+                break;
+            }
+            flexible.retain(|i| !too_small.contains(i));
+            if flexible.is_empty() {
+                break;
+            }
+        }
+    } else if free > 0.0 {
+        let autos: Vec<usize> = (0..sizes.len()).filter(|i| sizes[*i].auto).collect();
+        if !autos.is_empty() {
+            let share = free / autos.len() as f32;
+            for i in autos {
+                sizes[i].base += share;
+            }
+        }
+    }
+
+    sizes.iter().map(|t| t.base).collect()
+}
+
+//the size a track can not get smaller than by itself
+fn track_min(track: &Track) -> f32 {
+    match track {
+        Track::Pixel(_p) => *_p,
+        Track::MinMax(min, _) => track_min(min),
+        _ => 0.0,
+    }
+}
+
+//Smallest length of one axis that fits the minimums of the elements on
+//it. `spans` is the (start, span, min size) of every element placed on
+//that axis, an element spanning tracks that are too small for it widens
+//the last of them.
+fn min_length(tracks: &[Track], spans: &[(usize, usize, f32)], gap: f32) -> f32 {
+    let count = spans
+        .iter()
+        .map(|s| s.0 + s.1)
+        .max()
+        .unwrap_or(0)
+        .max(tracks.len());
+    let mut sizes: Vec<f32> = (0..count)
+        .map(|i| tracks.get(i).map_or(0.0, track_min))
+        .collect();
+    for (start, _, min) in spans.iter().filter(|s| s.1 == 1) {
+        sizes[*start] = sizes[*start].max(*min);
+    }
+    for (start, span, min) in spans.iter().filter(|s| s.1 > 1) {
+        let spanned: f32 = sizes[*start..start + span].iter().sum();
+        let deficit = min - spanned - gap * (span - 1) as f32;
+        if deficit > 0.0 {
+            sizes[start + span - 1] += deficit;
+        }
+    }
+    sizes.iter().sum::<f32>() + gap * count.saturating_sub(1) as f32
+}
+
+fn track_offsets(sizes: &[f32], gap: f32) -> Vec<f32> {
+    let mut offsets = vec![];
+    let mut pos = 0.0;
+    for s in sizes.iter() {
+        offsets.push(pos);
+        pos += s + gap;
+    }
+    offsets
+}
+
+//offset and size of an element of `size` in a cell of `cell` length
+fn align_in_cell(align: &CrossAlign, unit: &Unit, natural: f32, cell: f32) -> (f32, f32) {
+    let size = match unit {
+        Unit::Pixel(_p) => *_p,
+        Unit::Natural => natural,
+        _ => return (0.0, cell),
+    };
+    match align {
+        CrossAlign::Center => ((cell - size) / 2.0, size),
+        CrossAlign::End => (cell - size, size),
+        CrossAlign::Start => (0.0, size),
+        _ => (0.0, cell),
+    }
+}
+
+pub struct Grid {
+    ext_id: u64,
+    children: Vec<(Arc<Mutex<dyn Element>>, GridCell)>,
+    props: properties::Properties,
+    bounds: properties::Extent,
+    handlers: EventHandlers,
+    click: ClickState,
+    rows: Vec<Track>,
+    columns: Vec<Track>,
+    auto_row: Track,
+    auto_column: Track,
+    row_gap: f32,
+    column_gap: f32,
+    justify_items: CrossAlign,
+    align_items: CrossAlign,
+}
+
+impl Grid {
+    pub fn new() -> Self {
+        let mut props = properties::Properties::new();
+        props.default();
+        Grid {
+            ext_id: 0,
+            children: Vec::new(),
+            props,
+            bounds: properties::Extent::new(),
+            handlers: EventHandlers::new(),
+            click: ClickState::default(),
+            rows: vec![],
+            columns: vec![],
+            auto_row: Track::Natural,
+            auto_column: Track::Natural,
+            row_gap: 0.0,
+            column_gap: 0.0,
+            justify_items: CrossAlign::Stretch,
+            align_items: CrossAlign::Stretch,
+        }
+    }
+
+    pub fn set_rows(&mut self, rows: Vec<Track>) {
+        self.rows = rows;
+    }
+
+    pub fn set_columns(&mut self, columns: Vec<Track>) {
+        self.columns = columns;
+    }
+
+    //size of rows created for elements placed past the defined rows
+    pub fn set_auto_rows(&mut self, track: Track) {
+        self.auto_row = track;
+    }
+
+    //size of columns created for elements placed past the defined columns
+    pub fn set_auto_columns(&mut self, track: Track) {
+        self.auto_column = track;
+    }
+
+    pub fn set_gap(&mut self, gap: f32) {
+        self.row_gap = gap;
+        self.column_gap = gap;
+    }
+
+    pub fn set_row_gap(&mut self, gap: f32) {
+        self.row_gap = gap;
+    }
+
+    pub fn set_column_gap(&mut self, gap: f32) {
+        self.column_gap = gap;
+    }
+
+    //default alignment of elements in their cells. CrossAlign::Auto is
+    //treated as Stretch.
+    pub fn set_justify_items(&mut self, justify: CrossAlign) {
+        self.justify_items = justify;
+    }
+
+    pub fn set_align_items(&mut self, align: CrossAlign) {
+        self.align_items = align;
+    }
+
+    pub fn place(&mut self, e: Arc<Mutex<dyn Element>>, cell: GridCell) {
+        //a span of 0 set on the fields directly still takes one track
+        let cell = GridCell {
+            row_span: cell.row_span.max(1),
+            column_span: cell.column_span.max(1),
+            ..cell
+        };
+        self.children.push((e, cell));
+    }
+
+    //first free cell, going row by row
+    fn next_free_cell(&self) -> GridCell {
+        let columns = self.columns.len().max(1);
+        let mut i = 0;
+        loop {
+            let row = i / columns;
+            let column = i % columns;
+            let taken = self.children.iter().any(|(_, c)| {
+                row >= c.row
+                    && row < c.row + c.row_span
+                    && column >= c.column
+                    && column < c.column + c.column_span
+            });
+            if !taken {
+                return GridCell::new(row, column);
+            }
+            i += 1;
+        }
+    }
+
+    fn track_list(defined: &[Track], auto: &Track, count: usize) -> Vec<Track> {
+        let mut tracks = defined.to_vec();
+        while tracks.len() < count {
+            tracks.push(auto.clone());
+        }
+        tracks
+    }
+
+    fn resolve_align(&self, cell_align: &CrossAlign, default: &CrossAlign) -> CrossAlign {
+        match (cell_align, default) {
+            (CrossAlign::Auto, CrossAlign::Auto) => CrossAlign::Stretch,
+            (CrossAlign::Auto, _) => default.clone(),
+            _ => cell_align.clone(),
+        }
+    }
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Element for Grid {
+    fn get_ext_id(&self) -> u64 {
+        self.ext_id
+    }
+
+    fn get_kind(&self) -> &'static str {
+        "Grid"
+    }
+
+    fn set(&mut self, prop: properties::Property) {
+        self.props.set(prop);
+    }
+
+    fn get_properties(&self) -> properties::Properties {
+        self.props.clone()
+    }
+
+    fn render(
+        &mut self,
+        api: &RenderApi,
+        builder: &mut DisplayListBuilder,
+        extent: properties::Extent,
+        font_store: &mut font::FontStore,
+        _props: Option<Arc<properties::Properties>>,
+        gen: &mut properties::IdGenerator,
+    ) {
+        //the values children inherit from
+        let computed = Arc::new(style::computed("Grid", &self.props, &_props, vec![]));
+        let bgcolor = computed.get_bg_color();
+        let props = computed.resolved(extent.w, extent.h);
+
+        let width = container_size(
+            props.get_width(),
+            extent.w,
+            &props.get_min_width(),
+            &props.get_max_width(),
+        );
+        let height = container_size(
+            props.get_height(),
+            extent.h,
+            &props.get_min_height(),
+            &props.get_max_height(),
+        );
+
+        let mut row_count = self.rows.len();
+        let mut column_count = self.columns.len();
+        let mut row_spans = vec![];
+        let mut column_spans = vec![];
+        let mut units = vec![];
+        for (elm, cell) in self.children.iter() {
+            row_count = row_count.max(cell.row + cell.row_span);
+            column_count = column_count.max(cell.column + cell.column_span);
+            match elm.lock() {
+                Ok(ref _e) => {
+                    let raw = inherited(&**_e, &computed);
+                    let props = raw.resolved(width.unwrap_or(0.0), height.unwrap_or(0.0));
+                    let b = _e.get_bounds();
+                    let (w, h) = (props.get_width(), props.get_height());
+                    column_spans.push((
+                        cell.column,
+                        cell.column_span,
+                        intrinsic_size(&w, sane(b.w)),
+                    ));
+                    row_spans.push((cell.row, cell.row_span, intrinsic_size(&h, sane(b.h))));
+                    units.push((raw, sane(b.w), sane(b.h)));
+                }
+                Err(_err_str) => panic!("unable to lock element : {}", _err_str),
+            }
+        }
+
+        let columns = Grid::track_list(&self.columns, &self.auto_column, column_count);
+        let rows = Grid::track_list(&self.rows, &self.auto_row, row_count);
+        let column_sizes = size_tracks(&columns, &column_spans, width, self.column_gap);
+        let row_sizes = size_tracks(&rows, &row_spans, height, self.row_gap);
+        let column_offsets = track_offsets(&column_sizes, self.column_gap);
+        let row_offsets = track_offsets(&row_sizes, self.row_gap);
+
+        let content_w = column_sizes.iter().sum::<f32>()
+            + self.column_gap * (column_sizes.len() as f32 - 1.0).max(0.0);
+        let content_h =
+            row_sizes.iter().sum::<f32>() + self.row_gap * (row_sizes.len() as f32 - 1.0).max(0.0);
+
+        self.bounds = properties::Extent {
+            x: extent.x,
+            y: extent.y,
+            w: width.unwrap_or(content_w),
+            h: height.unwrap_or(content_h),
+            dpi: extent.dpi,
+        };
+
+        let _id = gen.get();
+        self.ext_id = _id;
+
+        let mut info = LayoutPrimitiveInfo::new(
+            (self.bounds.x, self.bounds.y).by(self.bounds.w, self.bounds.h),
+        );
+        info.tag = Some((_id, 0));
+        builder.push_rect(&info, bgcolor);
+
+        for (i, (elm, cell)) in self.children.iter().enumerate() {
+            let column_end = cell.column + cell.column_span - 1;
+            let row_end = cell.row + cell.row_span - 1;
+            let cell_w =
+                column_offsets[column_end] + column_sizes[column_end] - column_offsets[cell.column];
+            let cell_h = row_offsets[row_end] + row_sizes[row_end] - row_offsets[cell.row];
+
+            //percentages of a child are relative to its cell
+            let (ref raw, natural_w, natural_h) = units[i];
+            let props = raw.resolved(cell_w, cell_h);
+            let justify = self.resolve_align(&cell.justify, &self.justify_items);
+            let align = self.resolve_align(&cell.align, &self.align_items);
+            let (x_off, w) = align_in_cell(&justify, &props.get_width(), natural_w, cell_w);
+            let (y_off, h) = align_in_cell(&align, &props.get_height(), natural_h, cell_h);
+
+            let child_extent = properties::Extent {
+                x: extent.x + column_offsets[cell.column] + x_off,
+                y: extent.y + row_offsets[cell.row] + y_off,
+                w: given_size(&raw.get_width(), w, cell_w),
+                h: given_size(&raw.get_height(), h, cell_h),
+                dpi: extent.dpi,
+            };
+
+            match elm.lock() {
+                Ok(ref mut elm) => {
+                    validate_element(&**elm);
+                    elm.render(
+                        api,
+                        builder,
+                        child_extent,
+                        font_store,
+                        Some(computed.clone()),
+                        gen,
+                    );
+                }
+                Err(_err_str) => panic!("unable to lock element : {}", _err_str),
+            }
+        }
+    }
+
+    fn get_bounds(&self) -> properties::Extent {
+        self.bounds.clone()
+    }
+
+    fn get_min_size(&self) -> (f32, f32) {
+        let mut columns = vec![];
+        let mut rows = vec![];
+        for (elm, cell) in self.children.iter() {
+            if let Ok(elm) = elm.lock() {
+                let (w, h) = elm.get_min_size();
+                columns.push((cell.column, cell.column_span, w));
+                rows.push((cell.row, cell.row_span, h));
+            }
+        }
+        let content = (
+            min_length(&self.columns, &columns, self.column_gap),
+            min_length(&self.rows, &rows, self.row_gap),
+        );
+        min_size(&self.props, content)
+    }
+
+    fn on_primitive_event(&mut self, ext_ids: &[ItemTag], e: PrimitiveEvent) -> bool {
+        let mut handled = false;
+        for (_child_elm, _) in self.children.iter_mut() {
+            match (&e, _child_elm.lock()) {
+                (PrimitiveEvent::SetFocus(_), Ok(ref mut _child_elm)) => {
+                    if ext_ids.len() > 1
+                        && ext_ids[0].0 == self.ext_id
+                        && ext_ids[1].0 == _child_elm.get_ext_id()
+                    {
+                        _child_elm
+                            .on_primitive_event(&ext_ids[1..], PrimitiveEvent::SetFocus(true));
+                    } else {
+                        _child_elm.on_primitive_event(&[], PrimitiveEvent::SetFocus(false));
+                    }
+                }
+                (PrimitiveEvent::Char(_), Ok(ref mut _child_elm))
+                | (PrimitiveEvent::KeyInput(..), Ok(ref mut _child_elm)) => {
+                    handled = _child_elm.on_primitive_event(&[], e.clone());
+                    if handled {
+                        break;
+                    }
+                }
+                (PrimitiveEvent::HoverBegin(_), Ok(ref mut _child_elm)) => {
+                    _child_elm.on_primitive_event(&[], e.clone());
+                }
+                (PrimitiveEvent::HoverEnd(_), Ok(ref mut _child_elm)) => {
+                    _child_elm.on_primitive_event(&[], e.clone());
+                }
+                (_, Ok(ref mut _child_elm)) => {
+                    if !handled {
+                        if ext_ids.len() == 1 {
+                            handled = _child_elm.on_primitive_event(&[], e.clone());
+                        } else if ext_ids.len() > 1 {
+                            handled = _child_elm.on_primitive_event(&ext_ids[1..], e.clone());
+                        }
+                    }
+                }
+                (_, Err(_err_str)) => {
+                    //this should be unreachable
+                    panic!("unable to lock element : {}", _err_str)
+                }
+            }
+        }
+        // if none of the children handled the event
+        // see if you can handle it here
+        let over = !handled && !ext_ids.is_empty() && ext_ids[0].0 == self.ext_id;
+        if self.click.update(self.ext_id, over, &e) {
+            if let PrimitiveEvent::Button(_p, _b, _s, m) = e {
+                handled = self.exec_handler(ElementEvent::Clicked, &m);
+            }
+        }
+        handled
+    }
+
+    fn set_handler(&mut self, _e: ElementEvent, _f: EventFn) {
+        self.handlers.insert(_e, _f);
+    }
+
+    fn exec_handler(&mut self, _e: ElementEvent, _d: &dyn Any) -> bool {
+        let h = self.handlers.get_mut(&_e).cloned();
+        if let Some(mut h) = h {
+            h.call(self, _d)
+        } else {
+            false
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl HasChildren for Grid {
+    fn get_child(&self, i: u32) -> Option<Arc<Mutex<dyn Element>>> {
+        self.children.get(i as usize).map(|(e, _)| e.clone())
+    }
+
+    //places the element in the next free cell
+    fn append(&mut self, e: Arc<Mutex<dyn Element>>) -> Option<Arc<Mutex<dyn Element>>> {
+        let cell = self.next_free_cell();
+        self.children.push((e, cell));
+        None
+    }
+}
@@ -0,0 +1,140 @@
+//what an edit did to the text
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EditKind {
+    Insert,
+    Delete,
+    Paste,
+    Replace, //typing over a selection or setting the whole value
+}
+
+//The chars `removed` at `offset` and the ones `inserted` in their place,
+//with the selection as (anchor, caret) before and after.
+#[derive(Clone, Debug)]
+pub struct Edit {
+    pub kind: EditKind,
+    pub offset: usize,
+    pub removed: Vec<char>,
+    pub inserted: Vec<char>,
+    pub before: (usize, usize),
+    pub after: (usize, usize),
+}
+
+impl Edit {
+    //a single key typed or deleted with nothing selected
+    fn is_keystroke(&self) -> bool {
+        self.before.0 == self.before.1 && self.removed.len() + self.inserted.len() == 1
+    }
+
+    //Takes in `next` when it goes on typing the same word, or deleting
+    //next to where this edit deleted. A word and the spaces after it are
+    //undone together.
+    fn merge(&mut self, next: &Edit) -> bool {
+        if !next.is_keystroke() {
+            return false;
+        }
+        match (self.kind, next.kind) {
+            (EditKind::Insert, EditKind::Insert) | (EditKind::Replace, EditKind::Insert) => {
+                if self.offset + self.inserted.len() != next.offset {
+                    return false;
+                }
+                let starts_word = match (self.inserted.last(), next.inserted.first()) {
+                    (Some(a), Some(b)) => a.is_whitespace() && !b.is_whitespace(),
+                    _ => false,
+                };
+                if starts_word {
+                    return false;
+                }
+                self.inserted.extend(next.inserted.iter().cloned());
+            }
+            (EditKind::Delete, EditKind::Delete) if self.inserted.is_empty() => {
+                if next.offset + next.removed.len() == self.offset {
+                    //backspace
+                    let mut removed = next.removed.clone();
+                    removed.extend(self.removed.iter().cloned());
+                    self.removed = removed;
+                    self.offset = next.offset;
+                } else if next.offset == self.offset {
+                    //delete
+                    self.removed.extend(next.removed.iter().cloned());
+                } else {
+                    return false;
+                }
+            }
+            _ => return false,
+        }
+        self.after = next.after;
+        true
+    }
+}
+
+//Edits that can be undone and then redone, at most `depth` of them.
+#[derive(Clone, Debug)]
+pub struct History {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    depth: usize,
+}
+
+impl History {
+    pub fn new(depth: usize) -> History {
+        History {
+            undo: vec![],
+            redo: vec![],
+            depth,
+        }
+    }
+
+    pub fn set_depth(&mut self, depth: usize) {
+        self.depth = depth;
+        if self.undo.len() > depth {
+            let extra = self.undo.len() - depth;
+            self.undo.drain(..extra);
+        }
+        self.redo.truncate(depth);
+    }
+
+    pub fn get_depth(&self) -> usize {
+        self.depth
+    }
+
+    //a new edit, which can not be followed by what was undone before it
+    pub fn record(&mut self, edit: Edit) {
+        self.redo.clear();
+        if let Some(last) = self.undo.last_mut() {
+            if last.merge(&edit) {
+                return;
+            }
+        }
+        self.undo.push(edit);
+        if self.undo.len() > self.depth {
+            self.undo.remove(0);
+        }
+    }
+
+    //the edit to revert
+    pub fn undo(&mut self) -> Option<Edit> {
+        let edit = self.undo.pop()?;
+        self.redo.push(edit.clone());
+        Some(edit)
+    }
+
+    //the edit to make again
+    pub fn redo(&mut self) -> Option<Edit> {
+        let edit = self.redo.pop()?;
+        self.undo.push(edit.clone());
+        Some(edit)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
@@ -0,0 +1,281 @@
+use std::sync::{Arc, Mutex};
+
+use crate::elements::element::Element;
+use crate::elements::flex::Justify;
+use crate::gui::properties::{CrossAlign, Properties, Unit, VerticalAlign};
+use crate::gui::style;
+
+//The properties of `elm` as it renders inside a parent that renders
+//with `parent`, so lengths such as Em resolve against what it inherits.
+pub fn inherited(elm: &dyn Element, parent: &Arc<Properties>) -> Properties {
+    style::computed(
+        elm.get_kind(),
+        &elm.get_properties(),
+        &Some(parent.clone()),
+        vec![],
+    )
+}
+
+//resolves a min/max bound. Lengths are expected to be resolved to
+//Pixel already and Stretch has no meaning for a bound, so anything
+//else uses the fallback.
+pub fn resolve_bound(unit: &Unit, parent: f32, natural: f32, fallback: f32) -> f32 {
+    match unit {
+        Unit::Pixel(_p) => *_p,
+        Unit::Extent => parent,
+        Unit::Natural => natural,
+        _ => fallback,
+    }
+}
+
+//bounds of elements that have not been rendered yet can be NaN
+pub fn sane(v: f32) -> f32 {
+    if v.is_nan() || v.is_infinite() {
+        0.0
+    } else {
+        v
+    }
+}
+
+//keeps `size` between an element's min and max. A bound relative to
+//the parent uses `parent`.
+pub fn clamp_size(size: f32, min: &Unit, max: &Unit, parent: f32) -> f32 {
+    let min = resolve_bound(min, parent, 0.0, 0.0);
+    let max = resolve_bound(max, parent, std::f32::INFINITY, std::f32::INFINITY);
+    size.min(max).max(min)
+}
+
+//Smallest size an element with `props` can be laid out in, margins
+//included, when its content needs at least `content`. Only pixel values
+//count, anything relative to the space given shrinks along with it.
+pub fn min_size(props: &Properties, content: (f32, f32)) -> (f32, f32) {
+    let props = props.resolved(0.0, 0.0);
+    let px = |u: Unit| match u {
+        Unit::Pixel(_p) if _p.is_finite() => _p,
+        _ => 0.0,
+    };
+    let w = px(props.get_min_width())
+        .max(px(props.get_width()))
+        .max(content.0);
+    let h = px(props.get_min_height())
+        .max(px(props.get_height()))
+        .max(content.1);
+    (
+        px(props.get_left()) + w + px(props.get_right()),
+        px(props.get_top()) + h + px(props.get_bottom()),
+    )
+}
+
+//The smallest content of `children`, their minimums added up along the
+//axes they are laid out one after another on and the largest taken
+//along the others.
+pub fn children_min_size(
+    children: &[Arc<Mutex<dyn Element>>],
+    add_w: bool,
+    add_h: bool,
+) -> (f32, f32) {
+    let mut size = (0.0f32, 0.0f32);
+    for elm in children.iter() {
+        if let Ok(elm) = elm.lock() {
+            let (w, h) = elm.get_min_size();
+            size.0 = if add_w { size.0 + w } else { size.0.max(w) };
+            size.1 = if add_h { size.1 + h } else { size.1.max(h) };
+        }
+    }
+    size
+}
+
+//size of a container along an axis, None if it depends on the content
+pub fn container_size(unit: Unit, available: f32, min: &Unit, max: &Unit) -> Option<f32> {
+    let size = match unit {
+        Unit::Pixel(_p) => _p,
+        Unit::Natural => return None,
+        _ => available,
+    };
+    Some(clamp_size(size, min, max, available))
+}
+
+//final size of an element along an axis from its unit, the extent it
+//was given and the size of its content, kept within its min and max
+pub fn element_size(unit: &Unit, min: &Unit, max: &Unit, available: f32, natural: f32) -> f32 {
+    let size = match unit {
+        Unit::Extent => available,
+        Unit::Pixel(_p) => *_p,
+        Unit::Stretch(_s) => _s * available,
+        _ => natural,
+    };
+    clamp_size(size, min, max, available)
+}
+
+//Elements resolve percentages against the extent they are given, so a
+//child with one gets the size of its parent rather than the size worked
+//out for it.
+pub fn given_size(unit: &Unit, size: f32, parent: f32) -> f32 {
+    if unit.is_relative() {
+        parent
+    } else {
+        size
+    }
+}
+
+//a stretch factor with the min and max of the element it belongs to,
+//ready for `distribute`. Anything that does not stretch gets factor 0.
+pub fn stretch_item(unit: &Unit, min: &Unit, max: &Unit, parent: f32) -> (f32, f32, f32) {
+    let factor = match unit {
+        Unit::Stretch(_s) => *_s,
+        _ => 0.0,
+    };
+    (
+        factor,
+        resolve_bound(min, parent, 0.0, 0.0),
+        resolve_bound(max, parent, std::f32::INFINITY, std::f32::INFINITY),
+    )
+}
+
+//Shares `space` between (factor, min, max) items in proportion to their
+//factors. An item whose share falls outside its bounds is fixed at the
+//bound and the space left over is shared again between the others.
+pub fn distribute(space: f32, items: &[(f32, f32, f32)]) -> Vec<f32> {
+    let mut sizes = vec![0.0; items.len()];
+    let mut frozen: Vec<bool> = items.iter().map(|i| i.0 <= 0.0).collect();
+    let mut space = space.max(0.0);
+
+    loop {
+        let total: f32 = items
+            .iter()
+            .zip(frozen.iter())
+            .filter(|(_, f)| !**f)
+            .map(|(i, _)| i.0)
+            .sum();
+        if total <= 0.0 {
+            break;
+        }
+        let factor = (space / total).max(0.0);
+
+        //positive when mins were hit, negative when maxes were
+        let mut violation = 0.0;
+        for (i, item) in items.iter().enumerate() {
+            if !frozen[i] {
+                let target = item.0 * factor;
+                sizes[i] = target.min(item.2).max(item.1);
+                violation += sizes[i] - target;
+            }
+        }
+        if violation.abs() < 0.01 {
+            break;
+        }
+
+        for (i, item) in items.iter().enumerate() {
+            if frozen[i] {
+                continue;
+            }
+            let target = item.0 * factor;
+            if (violation > 0.0 && sizes[i] > target) || (violation < 0.0 && sizes[i] < target) {
+                frozen[i] = true;
+                space -= sizes[i];
+            }
+        }
+    }
+
+    sizes
+}
+
+//natural size an element contributes to a track or line. Stretchy
+//elements take whatever they are given so they contribute nothing.
+pub fn intrinsic_size(unit: &Unit, natural: f32) -> f32 {
+    match unit {
+        Unit::Pixel(_p) => *_p,
+        Unit::Stretch(_) | Unit::Extent => 0.0,
+        _ => natural,
+    }
+}
+
+//offset and size along one axis of an element placed with a leading
+//margin, a size and a trailing margin in `available` space. Stretch
+//values share whatever the pixel values leave over.
+pub fn place(before: &Unit, size: &Unit, after: &Unit, available: f32, natural: f32) -> (f32, f32) {
+    let mut pixel = 0.0;
+    let mut stretchy = 0.0;
+    for u in [before, size, after].iter() {
+        match u {
+            Unit::Pixel(_p) => pixel += *_p,
+            Unit::Stretch(_s) => stretchy += *_s,
+            Unit::Extent => pixel += available,
+            _ => (),
+        }
+    }
+    if let Unit::Natural = size {
+        pixel += natural;
+    }
+
+    let mut factor = (available - pixel).max(0.0) / stretchy;
+    if factor.is_nan() || factor.is_infinite() {
+        factor = 0.0;
+    }
+
+    let resolve = |u: &Unit, natural: f32| match u {
+        Unit::Pixel(_p) => *_p,
+        Unit::Stretch(_s) => *_s * factor,
+        Unit::Extent => available,
+        _ => natural,
+    };
+
+    (resolve(before, 0.0), resolve(size, natural))
+}
+
+//top of text that is `text_h` high in an element spanning `top` to
+//`top + height`. `baseline` is the distance from the top of a line to
+//its baseline.
+pub fn text_top(align: &VerticalAlign, top: f32, height: f32, text_h: f32, baseline: f32) -> f32 {
+    let text_h = sane(text_h);
+    match align {
+        VerticalAlign::Top => top,
+        VerticalAlign::Center => top + (height - text_h) / 2.0,
+        VerticalAlign::Bottom => top + height - text_h,
+        VerticalAlign::Baseline => top + height / 2.0 - baseline,
+    }
+}
+
+//Size of a child across a box, worked out before it is rendered so it
+//can be placed. A size that depends on the content comes from the last
+//render, the bool is true for those.
+pub fn cross_size(unit: &Unit, min: &Unit, max: &Unit, space: f32, last: f32) -> (f32, bool) {
+    let natural = match unit {
+        Unit::Pixel(_) | Unit::Stretch(_) | Unit::Extent => false,
+        _ => true,
+    };
+    (element_size(unit, min, max, space, sane(last)), natural)
+}
+
+//offset of a child of `size` on the cross axis of a box that has
+//`space` for it. Stretch and Auto keep the child at the start as it is
+//given all of the space.
+pub fn cross_offset(align: &CrossAlign, size: f32, space: f32) -> f32 {
+    let free = (space - sane(size)).max(0.0);
+    match align {
+        CrossAlign::Center => free / 2.0,
+        CrossAlign::End => free,
+        _ => 0.0,
+    }
+}
+
+//offset of the first of `count` items and the extra space between
+//items when `free` space is left over on a line
+pub fn justify_offsets(justify: &Justify, free: f32, count: usize) -> (f32, f32) {
+    let n = count as f32;
+    match justify {
+        Justify::Start => (0.0, 0.0),
+        Justify::End => (free, 0.0),
+        Justify::Center => (free / 2.0, 0.0),
+        _ if free <= 0.0 => (0.0, 0.0),
+        Justify::SpaceBetween => {
+            if count > 1 {
+                (0.0, free / (n - 1.0))
+            } else {
+                (0.0, 0.0)
+            }
+        }
+        Justify::SpaceAround => (free / n / 2.0, free / n),
+        Justify::SpaceEvenly => (free / (n + 1.0), free / (n + 1.0)),
+    }
+}
@@ -1,15 +1,34 @@
 mod button;
+mod codeeditor;
 mod element;
+mod flex;
+mod grid;
 mod hbox;
+mod history;
 mod image;
+mod layout;
 mod scrollbox;
+mod splitpane;
+mod stack;
+mod syntax;
 mod textbox;
+mod validation;
 mod vbox;
+mod wrappanel;
 
 pub use self::button::Button;
+pub use self::codeeditor::{CodeEditor, TextEdit};
 pub use self::element::*;
+pub use self::flex::{Flex, FlexDirection, FlexWrap, Justify};
+pub use self::grid::{Grid, GridCell, Track};
 pub use self::hbox::HBox;
+pub use self::history::{Edit, EditKind, History};
 pub use self::image::*;
 pub use self::scrollbox::ScrollBox;
-pub use self::textbox::TextBox;
+pub use self::splitpane::{SplitOrientation, SplitPane};
+pub use self::stack::Stack;
+pub use self::syntax::{SimpleTokenizer, Span, TokenKind, Tokenizer};
+pub use self::textbox::{TextBox, TextChange};
+pub use self::validation::{CharFilter, Mask};
 pub use self::vbox::VBox;
+pub use self::wrappanel::WrapPanel;
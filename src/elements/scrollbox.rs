@@ -5,8 +5,11 @@ use std::sync::{Arc, Mutex};
 use webrender::api::*;
 
 use crate::elements::element::*;
+use crate::elements::layout::*;
 use crate::gui::font;
 use crate::gui::properties;
+use crate::gui::style;
+use crate::gui::window;
 use crate::util::*;
 
 pub struct ScrollBox {
@@ -16,6 +19,7 @@ pub struct ScrollBox {
     bounds: properties::Extent,
     content: properties::Extent,
     handlers: EventHandlers,
+    click: ClickState,
 }
 
 impl ScrollBox {
@@ -41,15 +45,31 @@ impl ScrollBox {
                 dpi: 0.0,
             },
             handlers: EventHandlers::new(),
+            click: ClickState::default(),
         }
     }
 }
 
+lazy_static! {
+    static ref REVEAL: Mutex<Option<properties::Extent>> = Mutex::new(None);
+}
+
+//Asks the ScrollBox around the caller to scroll until `rect` can be seen,
+//such as a caret that moved. Elements call it while they render and the
+//innermost ScrollBox takes it.
+pub fn reveal(rect: properties::Extent) {
+    *REVEAL.lock().unwrap() = Some(rect);
+}
+
 impl Element for ScrollBox {
     fn get_ext_id(&self) -> u64 {
         self.ext_id
     }
 
+    fn get_kind(&self) -> &'static str {
+        "ScrollBox"
+    }
+
     fn render(
         &mut self,
         api: &RenderApi,
@@ -59,7 +79,25 @@ impl Element for ScrollBox {
         _props: Option<Arc<properties::Properties>>,
         gen: &mut properties::IdGenerator,
     ) {
-        let bgcolor = self.props.get_bg_color();
+        //the values children inherit from
+        let computed = Arc::new(style::computed("ScrollBox", &self.props, &_props, vec![]));
+        let bgcolor = computed.get_bg_color();
+        let props = computed.resolved(extent.w, extent.h);
+        let extent = properties::Extent {
+            w: clamp_size(
+                extent.w,
+                &props.get_min_width(),
+                &props.get_max_width(),
+                extent.w,
+            ),
+            h: clamp_size(
+                extent.h,
+                &props.get_min_height(),
+                &props.get_max_height(),
+                extent.h,
+            ),
+            ..extent
+        };
 
         let _id = gen.get();
         self.ext_id = _id;
@@ -98,9 +136,11 @@ impl Element for ScrollBox {
         );
         builder.push_clip_id(scroll_frame);
 
+        REVEAL.lock().unwrap().take();
         if let Some(ref mut elm) = self.child {
             match elm.lock() {
                 Ok(ref mut elm) => {
+                    validate_element(&**elm);
                     elm.render(
                         api,
                         builder,
@@ -112,7 +152,7 @@ impl Element for ScrollBox {
                             dpi: extent.dpi,
                         },
                         font_store,
-                        None,
+                        Some(computed.clone()),
                         gen,
                     );
                     bounds = elm.get_bounds();
@@ -124,6 +164,11 @@ impl Element for ScrollBox {
         builder.pop_clip_id(); //scroll frame
         builder.pop_stacking_context();
 
+        //children render from the top left of the scroll frame
+        if let Some(rect) = REVEAL.lock().unwrap().take() {
+            window::reveal_in_frame(ExternalScrollId(_id, pipeline_id), rect, extent.w, extent.h);
+        }
+
         self.content = bounds;
     }
 
@@ -158,7 +203,8 @@ impl Element for ScrollBox {
                         _child_elm.on_primitive_event(&[], PrimitiveEvent::SetFocus(false));
                     }
                 }
-                (PrimitiveEvent::Char(_c), Ok(ref mut _child_elm)) => {
+                (PrimitiveEvent::Char(_), Ok(ref mut _child_elm))
+                | (PrimitiveEvent::KeyInput(..), Ok(ref mut _child_elm)) => {
                     handled = _child_elm.on_primitive_event(&[], e.clone());
                 }
                 // XXX: These used to be unreachable; they trigger a panic in the WRRenderBackend thread
@@ -185,7 +231,8 @@ impl Element for ScrollBox {
         }
         // if none of the children handled the event
         // see if you can handle it here
-        if !handled {
+        let over = !handled && !ext_ids.is_empty() && ext_ids[0].0 == self.ext_id;
+        if self.click.update(self.ext_id, over, &e) {
             if let PrimitiveEvent::Button(_p, _b, _s, m) = e {
                 handled = self.exec_handler(ElementEvent::Clicked, &m);
             }
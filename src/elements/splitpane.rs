@@ -0,0 +1,484 @@
+use std::any::Any;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use webrender::api::*;
+
+use crate::data::{Action, Observable, Update};
+use crate::elements::element::*;
+use crate::elements::layout::*;
+use crate::gui::font;
+use crate::gui::properties;
+use crate::gui::style;
+use crate::util::*;
+
+//two presses on the splitter within this time collapse or restore it
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SplitOrientation {
+    Horizontal, //sides next to each other
+    Vertical,   //one side above the other
+}
+
+//Two children with a splitter bar between them that can be dragged to
+//resize them. The first child's share of the space is kept as a ratio
+//so it survives resizing of the window. The sides respect the MinWidth
+//and MaxWidth (MinHeight and MaxHeight when vertical) of the children.
+//A double click on the splitter collapses the first side and another
+//one restores it.
+pub struct SplitPane {
+    ext_id: u64,
+    children: Vec<Arc<Mutex<dyn Element>>>,
+    props: properties::Properties,
+    bounds: properties::Extent,
+    handlers: EventHandlers,
+    click: ClickState,
+    orientation: SplitOrientation,
+    ratio: f32,
+    splitter_size: f32,
+    collapsed: bool,
+    //offset of the cursor in to the splitter while it is dragged
+    dragging: Option<f32>,
+    hovering: bool,
+    last_press: Option<Instant>,
+    //where the splitter bar was drawn last, relative to the pane
+    splitter_pos: f32,
+    observer: Option<Arc<Mutex<Observable<f32>>>>,
+}
+
+impl SplitPane {
+    pub fn new(orientation: SplitOrientation) -> Self {
+        let mut props = properties::Properties::new();
+        props.default();
+        SplitPane {
+            ext_id: 0,
+            children: Vec::new(),
+            props,
+            bounds: properties::Extent::new(),
+            handlers: EventHandlers::new(),
+            click: ClickState::default(),
+            orientation,
+            ratio: 0.5,
+            splitter_size: 6.0,
+            collapsed: false,
+            dragging: None,
+            hovering: false,
+            last_press: None,
+            splitter_pos: 0.0,
+            observer: None,
+        }
+    }
+
+    pub fn set_orientation(&mut self, orientation: SplitOrientation) {
+        self.orientation = orientation;
+    }
+
+    pub fn get_orientation(&self) -> SplitOrientation {
+        self.orientation.clone()
+    }
+
+    //share of the space, without the splitter, given to the first child
+    pub fn set_ratio(&mut self, ratio: f32) {
+        self.ratio = ratio.max(0.0).min(1.0);
+    }
+
+    pub fn get_ratio(&self) -> f32 {
+        self.ratio
+    }
+
+    pub fn set_splitter_size(&mut self, size: f32) {
+        self.splitter_size = size.max(0.0);
+    }
+
+    pub fn set_collapsed(&mut self, collapsed: bool) {
+        self.collapsed = collapsed;
+    }
+
+    pub fn is_collapsed(&self) -> bool {
+        self.collapsed
+    }
+
+    //Keeps the ratio in `ratio` so it can be saved and restored by the
+    //application. The current value is used right away and the
+    //observable is updated whenever a drag ends.
+    pub fn bind_ratio(&mut self, ratio: Arc<Mutex<Observable<f32>>>) {
+        if let Ok(r) = ratio.lock() {
+            self.set_ratio(r.get_value());
+        }
+        self.observer = Some(ratio);
+    }
+
+    fn notify(&mut self) {
+        if let Some(ref observer) = self.observer {
+            if let Ok(mut o) = observer.lock() {
+                o.update(Action::Update(self.ratio));
+            }
+        }
+    }
+
+    fn is_horizontal(&self) -> bool {
+        self.orientation == SplitOrientation::Horizontal
+    }
+
+    //min and max of a child along the split
+    fn limits(&self, i: usize, available: f32, extent: &properties::Extent) -> (f32, f32) {
+        match self.children.get(i) {
+            Some(elm) => match elm.lock() {
+                Ok(ref elm) => {
+                    let props = elm.get_properties().resolved(extent.w, extent.h);
+                    let (min, max) = if self.is_horizontal() {
+                        (props.get_min_width(), props.get_max_width())
+                    } else {
+                        (props.get_min_height(), props.get_max_height())
+                    };
+                    let min = resolve_bound(&min, available, 0.0, 0.0);
+                    let max = resolve_bound(&max, available, available, available);
+                    (min, max.max(min))
+                }
+                Err(_err_str) => panic!("unable to lock element : {}", _err_str),
+            },
+            None => (0.0, available),
+        }
+    }
+
+    //size of the first side out of `available`
+    fn first_size(&self, available: f32, extent: &properties::Extent) -> f32 {
+        if self.collapsed {
+            return 0.0;
+        }
+        let (first_min, first_max) = self.limits(0, available, extent);
+        let (second_min, second_max) = self.limits(1, available, extent);
+
+        let first = (self.ratio * available).min(first_max).max(first_min);
+        //the second side's limits move the splitter back when they can
+        let second = (available - first).min(second_max).max(second_min);
+        (available - second)
+            .min(first_max)
+            .max(first_min)
+            .min(available)
+    }
+
+    fn main_pos(&self, p: &properties::Position) -> f32 {
+        if self.is_horizontal() {
+            p.x - self.bounds.x
+        } else {
+            p.y - self.bounds.y
+        }
+    }
+
+    fn available(&self) -> f32 {
+        let main = if self.is_horizontal() {
+            self.bounds.w
+        } else {
+            self.bounds.h
+        };
+        (main - self.splitter_size).max(0.0)
+    }
+
+    fn on_splitter(&self, ext_ids: &[ItemTag]) -> bool {
+        ext_ids.contains(&(self.ext_id, 1))
+    }
+
+    //states stylesheet selectors can match
+    fn states(&self) -> Vec<style::State> {
+        let mut states = vec![];
+        if self.hovering {
+            states.push(style::State::Hover);
+        }
+        if self.dragging.is_some() {
+            states.push(style::State::Active);
+        }
+        states
+    }
+}
+
+impl Default for SplitPane {
+    fn default() -> Self {
+        Self::new(SplitOrientation::Horizontal)
+    }
+}
+
+impl Element for SplitPane {
+    fn get_ext_id(&self) -> u64 {
+        self.ext_id
+    }
+
+    fn get_kind(&self) -> &'static str {
+        "SplitPane"
+    }
+
+    fn set(&mut self, prop: properties::Property) {
+        self.props.set(prop);
+    }
+
+    fn get_properties(&self) -> properties::Properties {
+        self.props.clone()
+    }
+
+    fn render(
+        &mut self,
+        api: &RenderApi,
+        builder: &mut DisplayListBuilder,
+        extent: properties::Extent,
+        font_store: &mut font::FontStore,
+        _props: Option<Arc<properties::Properties>>,
+        gen: &mut properties::IdGenerator,
+    ) {
+        //the values children inherit from
+        let computed = Arc::new(style::computed(
+            "SplitPane",
+            &self.props,
+            &_props,
+            self.states(),
+        ));
+        let bgcolor = computed.get_bg_color();
+        let mut color = computed.get_color();
+        if self.hovering {
+            color = computed.get_hover_color();
+        }
+        if self.dragging.is_some() {
+            color = computed.get_active_color();
+        }
+        let props = computed.resolved(extent.w, extent.h);
+
+        let w = container_size(
+            props.get_width(),
+            extent.w,
+            &props.get_min_width(),
+            &props.get_max_width(),
+        )
+        .unwrap_or(extent.w);
+        let h = container_size(
+            props.get_height(),
+            extent.h,
+            &props.get_min_height(),
+            &props.get_max_height(),
+        )
+        .unwrap_or(extent.h);
+
+        self.bounds = properties::Extent {
+            x: extent.x,
+            y: extent.y,
+            w,
+            h,
+            dpi: extent.dpi,
+        };
+
+        let _id = gen.get();
+        self.ext_id = _id;
+
+        let mut info = LayoutPrimitiveInfo::new((extent.x, extent.y).by(w, h));
+        info.tag = Some((_id, 0));
+        builder.push_rect(&info, bgcolor);
+
+        let available = self.available();
+        let first = self.first_size(available, &self.bounds);
+        let second = available - first;
+        self.splitter_pos = first;
+
+        let (first_extent, splitter, second_extent) = if self.is_horizontal() {
+            (
+                (extent.x, extent.y, first, h),
+                (extent.x + first, extent.y, self.splitter_size, h),
+                (extent.x + first + self.splitter_size, extent.y, second, h),
+            )
+        } else {
+            (
+                (extent.x, extent.y, w, first),
+                (extent.x, extent.y + first, w, self.splitter_size),
+                (extent.x, extent.y + first + self.splitter_size, w, second),
+            )
+        };
+
+        for (i, elm) in self.children.iter().enumerate() {
+            let (x, y, w, h) = if i == 0 { first_extent } else { second_extent };
+            //a collapsed side is not drawn at all
+            if i == 0 && self.collapsed {
+                continue;
+            }
+            match elm.lock() {
+                Ok(ref mut elm) => {
+                    validate_element(&**elm);
+                    elm.render(
+                        api,
+                        builder,
+                        properties::Extent {
+                            x,
+                            y,
+                            w,
+                            h,
+                            dpi: extent.dpi,
+                        },
+                        font_store,
+                        Some(computed.clone()),
+                        gen,
+                    );
+                }
+                Err(_err_str) => panic!("unable to lock element : {}", _err_str),
+            }
+        }
+
+        let mut info =
+            LayoutPrimitiveInfo::new((splitter.0, splitter.1).by(splitter.2, splitter.3));
+        info.tag = Some((_id, 1));
+        builder.push_rect(&info, color);
+    }
+
+    fn get_bounds(&self) -> properties::Extent {
+        self.bounds.clone()
+    }
+
+    //a collapsed first side takes no space
+    fn get_min_size(&self) -> (f32, f32) {
+        let children = if self.collapsed && !self.children.is_empty() {
+            &self.children[1..]
+        } else {
+            &self.children[..]
+        };
+        let content = match self.orientation {
+            SplitOrientation::Horizontal => {
+                let (w, h) = children_min_size(children, true, false);
+                (w + self.splitter_size, h)
+            }
+            SplitOrientation::Vertical => {
+                let (w, h) = children_min_size(children, false, true);
+                (w, h + self.splitter_size)
+            }
+        };
+        min_size(&self.props, content)
+    }
+
+    fn on_primitive_event(&mut self, ext_ids: &[ItemTag], e: PrimitiveEvent) -> bool {
+        match e {
+            PrimitiveEvent::Button(ref p, properties::Button::Left, ref s, _) => {
+                if *s == properties::ButtonState::Pressed && self.on_splitter(ext_ids) {
+                    let now = Instant::now();
+                    let double = match self.last_press {
+                        Some(t) => now.duration_since(t) < DOUBLE_CLICK,
+                        None => false,
+                    };
+                    if double {
+                        self.collapsed = !self.collapsed;
+                        self.last_press = None;
+                        self.notify();
+                    } else {
+                        self.last_press = Some(now);
+                        self.dragging = Some(self.main_pos(p) - self.splitter_pos);
+                    }
+                    return true;
+                }
+                if *s == properties::ButtonState::Released && self.dragging.is_some() {
+                    self.dragging = None;
+                    self.notify();
+                    return true;
+                }
+            }
+            PrimitiveEvent::CursorMoved(ref p) => {
+                if let Some(grab) = self.dragging {
+                    let available = self.available();
+                    if available > 0.0 {
+                        self.collapsed = false;
+                        self.set_ratio((self.main_pos(p) - grab) / available);
+                    }
+                    return true;
+                }
+            }
+            PrimitiveEvent::HoverBegin(ref n_tags) if self.on_splitter(n_tags) => {
+                self.hovering = true;
+            }
+            PrimitiveEvent::HoverEnd(ref o_tags) if self.on_splitter(o_tags) => {
+                self.hovering = false;
+            }
+            _ => (),
+        }
+
+        let mut handled = false;
+        for _child_elm in self.children.iter_mut() {
+            match (&e, _child_elm.lock()) {
+                (PrimitiveEvent::SetFocus(_), Ok(ref mut _child_elm)) => {
+                    if ext_ids.len() > 1
+                        && ext_ids[0].0 == self.ext_id
+                        && ext_ids[1].0 == _child_elm.get_ext_id()
+                    {
+                        _child_elm
+                            .on_primitive_event(&ext_ids[1..], PrimitiveEvent::SetFocus(true));
+                    } else {
+                        _child_elm.on_primitive_event(&[], PrimitiveEvent::SetFocus(false));
+                    }
+                }
+                (PrimitiveEvent::Char(_), Ok(ref mut _child_elm))
+                | (PrimitiveEvent::KeyInput(..), Ok(ref mut _child_elm)) => {
+                    handled = _child_elm.on_primitive_event(&[], e.clone());
+                    if handled {
+                        break;
+                    }
+                }
+                (PrimitiveEvent::HoverBegin(_), Ok(ref mut _child_elm)) => {
+                    _child_elm.on_primitive_event(&[], e.clone());
+                }
+                (PrimitiveEvent::HoverEnd(_), Ok(ref mut _child_elm)) => {
+                    _child_elm.on_primitive_event(&[], e.clone());
+                }
+                (_, Ok(ref mut _child_elm)) => {
+                    if !handled {
+                        if ext_ids.len() == 1 {
+                            handled = _child_elm.on_primitive_event(&[], e.clone());
+                        } else if ext_ids.len() > 1 {
+                            handled = _child_elm.on_primitive_event(&ext_ids[1..], e.clone());
+                        }
+                    }
+                }
+                (_, Err(_err_str)) => {
+                    //this should be unreachable
+                    panic!("unable to lock element : {}", _err_str)
+                }
+            }
+        }
+        // if none of the children handled the event
+        // see if you can handle it here
+        let over = !handled && !ext_ids.is_empty() && ext_ids[0].0 == self.ext_id;
+        if self.click.update(self.ext_id, over, &e) {
+            if let PrimitiveEvent::Button(_p, _b, _s, m) = e {
+                handled = self.exec_handler(ElementEvent::Clicked, &m);
+            }
+        }
+        handled
+    }
+
+    fn set_handler(&mut self, _e: ElementEvent, _f: EventFn) {
+        self.handlers.insert(_e, _f);
+    }
+
+    fn exec_handler(&mut self, _e: ElementEvent, _d: &dyn Any) -> bool {
+        let h = self.handlers.get_mut(&_e).cloned();
+        if let Some(mut h) = h {
+            h.call(self, _d)
+        } else {
+            false
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl HasChildren for SplitPane {
+    fn get_child(&self, i: u32) -> Option<Arc<Mutex<dyn Element>>> {
+        self.children.get(i as usize).cloned()
+    }
+
+    //the first two children are the sides, any more are handed back
+    fn append(&mut self, e: Arc<Mutex<dyn Element>>) -> Option<Arc<Mutex<dyn Element>>> {
+        if self.children.len() < 2 {
+            self.children.push(e);
+            None
+        } else {
+            Some(e)
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use std::any::Any;
+use std::sync::{Arc, Mutex};
+
+use webrender::api::*;
+
+use crate::elements::element::*;
+use crate::elements::layout::*;
+use crate::gui::font;
+use crate::gui::properties;
+use crate::gui::style;
+use crate::util::*;
+
+//A container whose children overlap. Every child is placed on its own
+//with Left/Width/Right and Top/Height/Bottom relative to the Stack, and
+//painted in ZIndex order. The child painted on top is the one that
+//receives mouse events.
+pub struct Stack {
+    ext_id: u64,
+    children: Vec<Arc<Mutex<dyn Element>>>,
+    props: properties::Properties,
+    bounds: properties::Extent,
+    handlers: EventHandlers,
+    click: ClickState,
+    //indices in to children, bottom first. updated every render.
+    order: Vec<usize>,
+}
+
+impl Stack {
+    pub fn new() -> Self {
+        let mut props = properties::Properties::new();
+        props.default();
+        Stack {
+            ext_id: 0,
+            children: Vec::new(),
+            props,
+            bounds: properties::Extent::new(),
+            handlers: EventHandlers::new(),
+            click: ClickState::default(),
+            order: vec![],
+        }
+    }
+
+    //the topmost child that was hit and the part of ext_ids that belongs to it
+    fn hit_child<'a>(&self, ext_ids: &'a [ItemTag]) -> Option<(usize, &'a [ItemTag])> {
+        if ext_ids.len() < 2 || ext_ids[0].0 != self.ext_id {
+            return None;
+        }
+
+        //the hit test lists tags bottom first, so every child's tags run
+        //up to where the next hit child's tags begin
+        let mut starts = vec![];
+        for (i, elm) in self.children.iter().enumerate() {
+            if let Ok(ref elm) = elm.lock() {
+                let id = elm.get_ext_id();
+                if let Some(pos) = ext_ids.iter().position(|t| t.0 == id) {
+                    starts.push((pos, i));
+                }
+            }
+        }
+        starts.sort();
+
+        let top = self
+            .order
+            .iter()
+            .rev()
+            .find(|i| starts.iter().any(|s| s.1 == **i))?;
+        let k = starts.iter().position(|s| s.1 == *top)?;
+        let begin = starts[k].0;
+        let end = if k + 1 < starts.len() {
+            starts[k + 1].0
+        } else {
+            ext_ids.len()
+        };
+
+        Some((*top, &ext_ids[begin..end]))
+    }
+}
+
+impl Default for Stack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Element for Stack {
+    fn get_ext_id(&self) -> u64 {
+        self.ext_id
+    }
+
+    fn get_kind(&self) -> &'static str {
+        "Stack"
+    }
+
+    fn set(&mut self, prop: properties::Property) {
+        self.props.set(prop);
+    }
+
+    fn get_properties(&self) -> properties::Properties {
+        self.props.clone()
+    }
+
+    fn render(
+        &mut self,
+        api: &RenderApi,
+        builder: &mut DisplayListBuilder,
+        extent: properties::Extent,
+        font_store: &mut font::FontStore,
+        _props: Option<Arc<properties::Properties>>,
+        gen: &mut properties::IdGenerator,
+    ) {
+        //the values children inherit from
+        let computed = Arc::new(style::computed("Stack", &self.props, &_props, vec![]));
+        let bgcolor = computed.get_bg_color();
+        let props = computed.resolved(extent.w, extent.h);
+
+        let w = container_size(
+            props.get_width(),
+            extent.w,
+            &props.get_min_width(),
+            &props.get_max_width(),
+        )
+        .unwrap_or(extent.w);
+        let h = container_size(
+            props.get_height(),
+            extent.h,
+            &props.get_min_height(),
+            &props.get_max_height(),
+        )
+        .unwrap_or(extent.h);
+
+        self.bounds = properties::Extent {
+            x: extent.x,
+            y: extent.y,
+            w,
+            h,
+            dpi: extent.dpi,
+        };
+
+        let _id = gen.get();
+        self.ext_id = _id;
+
+        let mut info = LayoutPrimitiveInfo::new((extent.x, extent.y).by(w, h));
+        info.tag = Some((_id, 0));
+        builder.push_rect(&info, bgcolor);
+
+        //stable sort keeps insertion order for equal z-index
+        let mut order: Vec<(i32, usize)> = vec![];
+        for (i, elm) in self.children.iter().enumerate() {
+            match elm.lock() {
+                Ok(ref elm) => order.push((elm.get_properties().get_z_index(), i)),
+                Err(_err_str) => panic!("unable to lock element : {}", _err_str),
+            }
+        }
+        order.sort_by_key(|o| o.0);
+        self.order = order.iter().map(|o| o.1).collect();
+
+        for i in self.order.iter() {
+            match self.children[*i].lock() {
+                Ok(ref mut elm) => {
+                    let raw = inherited(&**elm, &computed);
+                    let props = raw.resolved(w, h);
+                    let b = elm.get_bounds();
+                    let (x, cw) = place(
+                        &props.get_left(),
+                        &props.get_width(),
+                        &props.get_right(),
+                        w,
+                        sane(b.w),
+                    );
+                    let (y, ch) = place(
+                        &props.get_top(),
+                        &props.get_height(),
+                        &props.get_bottom(),
+                        h,
+                        sane(b.h),
+                    );
+                    let child_extent = properties::Extent {
+                        x: extent.x + x,
+                        y: extent.y + y,
+                        w: given_size(&raw.get_width(), cw, w),
+                        h: given_size(&raw.get_height(), ch, h),
+                        dpi: extent.dpi,
+                    };
+                    validate_element(&**elm);
+                    elm.render(
+                        api,
+                        builder,
+                        child_extent,
+                        font_store,
+                        Some(computed.clone()),
+                        gen,
+                    );
+                }
+                Err(_err_str) => panic!("unable to lock element : {}", _err_str),
+            }
+        }
+    }
+
+    fn get_bounds(&self) -> properties::Extent {
+        self.bounds.clone()
+    }
+
+    fn get_min_size(&self) -> (f32, f32) {
+        min_size(&self.props, children_min_size(&self.children, false, false))
+    }
+
+    fn on_primitive_event(&mut self, ext_ids: &[ItemTag], e: PrimitiveEvent) -> bool {
+        let mut handled = false;
+        let hit = self.hit_child(ext_ids);
+
+        //children are asked topmost first
+        let order: Vec<usize> = self.order.iter().rev().cloned().collect();
+        for i in order {
+            let child_ids: &[ItemTag] = match hit {
+                Some((top, ids)) if top == i => ids,
+                _ => &[],
+            };
+            match (&e, self.children[i].lock()) {
+                (PrimitiveEvent::SetFocus(_), Ok(ref mut _child_elm)) => {
+                    if !child_ids.is_empty() {
+                        _child_elm.on_primitive_event(child_ids, PrimitiveEvent::SetFocus(true));
+                    } else {
+                        _child_elm.on_primitive_event(&[], PrimitiveEvent::SetFocus(false));
+                    }
+                }
+                (PrimitiveEvent::Char(_), Ok(ref mut _child_elm))
+                | (PrimitiveEvent::KeyInput(..), Ok(ref mut _child_elm)) => {
+                    handled = _child_elm.on_primitive_event(&[], e.clone());
+                    if handled {
+                        break;
+                    }
+                }
+                (PrimitiveEvent::HoverBegin(_), Ok(ref mut _child_elm)) => {
+                    _child_elm.on_primitive_event(&[], e.clone());
+                }
+                (PrimitiveEvent::HoverEnd(_), Ok(ref mut _child_elm)) => {
+                    _child_elm.on_primitive_event(&[], e.clone());
+                }
+                (_, Ok(ref mut _child_elm)) => {
+                    if !handled {
+                        handled = _child_elm.on_primitive_event(child_ids, e.clone());
+                    }
+                }
+                (_, Err(_err_str)) => {
+                    //this should be unreachable
+                    panic!("unable to lock element : {}", _err_str)
+                }
+            }
+        }
+        // if none of the children handled the event
+        // see if you can handle it here
+        let over = !handled && !ext_ids.is_empty() && ext_ids[0].0 == self.ext_id;
+        if self.click.update(self.ext_id, over, &e) {
+            if let PrimitiveEvent::Button(_p, _b, _s, m) = e {
+                handled = self.exec_handler(ElementEvent::Clicked, &m);
+            }
+        }
+        handled
+    }
+
+    fn set_handler(&mut self, _e: ElementEvent, _f: EventFn) {
+        self.handlers.insert(_e, _f);
+    }
+
+    fn exec_handler(&mut self, _e: ElementEvent, _d: &dyn Any) -> bool {
+        let h = self.handlers.get_mut(&_e).cloned();
+        if let Some(mut h) = h {
+            h.call(self, _d)
+        } else {
+            false
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl HasChildren for Stack {
+    fn get_child(&self, i: u32) -> Option<Arc<Mutex<dyn Element>>> {
+        self.children.get(i as usize).cloned()
+    }
+
+    fn append(&mut self, e: Arc<Mutex<dyn Element>>) -> Option<Arc<Mutex<dyn Element>>> {
+        self.children.push(e);
+        self.order.push(self.children.len() - 1);
+        None
+    }
+}
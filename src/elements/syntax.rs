@@ -0,0 +1,189 @@
+use webrender::api::ColorF;
+
+//what a span of text is, brackets in strings and comments do not pair
+//with those in code
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TokenKind {
+    Keyword,
+    String,
+    Number,
+    Comment,
+    Other,
+}
+
+//The chars of a line from `start` up to `end` drawn in `color`. Both are
+//offsets into the line as it is stored, before tabs are expanded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub kind: TokenKind,
+    pub color: ColorF,
+}
+
+//Colours the lines of a CodeEditor, one at a time from the top. `state`
+//is what the line before ended in, 0 for the first line, and the state
+//returned is passed on to the next line so constructs such as block
+//comments can span lines. What the numbers mean is up to the tokenizer.
+//Chars no span covers keep the editor's Color.
+pub trait Tokenizer: Send + Sync {
+    fn tokenize(&self, line: &[char], state: u32) -> (Vec<Span>, u32);
+}
+
+//a line of a SimpleTokenizer ends inside a block comment
+const IN_BLOCK_COMMENT: u32 = 1;
+
+//Keywords, numbers, quoted strings, line comments and block comments,
+//which is enough to make most languages easier to read.
+#[derive(Clone, Debug)]
+pub struct SimpleTokenizer {
+    pub keywords: Vec<String>,
+    pub line_comment: String,
+    pub block_comment: Option<(String, String)>,
+    pub keyword_color: ColorF,
+    pub string_color: ColorF,
+    pub number_color: ColorF,
+    pub comment_color: ColorF,
+}
+
+impl SimpleTokenizer {
+    pub fn new(keywords: &[&str], line_comment: &str) -> SimpleTokenizer {
+        SimpleTokenizer {
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            line_comment: line_comment.to_string(),
+            block_comment: None,
+            keyword_color: ColorF::new(0.6, 0.2, 0.7, 1.0),
+            string_color: ColorF::new(0.2, 0.55, 0.2, 1.0),
+            number_color: ColorF::new(0.8, 0.45, 0.1, 1.0),
+            comment_color: ColorF::new(0.5, 0.5, 0.5, 1.0),
+        }
+    }
+
+    pub fn rust() -> SimpleTokenizer {
+        let mut tokenizer = SimpleTokenizer::new(
+            &[
+                "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false",
+                "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+                "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+                "true", "type", "unsafe", "use", "where", "while", "dyn",
+            ],
+            "//",
+        );
+        tokenizer.block_comment = Some((String::from("/*"), String::from("*/")));
+        tokenizer
+    }
+
+    //the end of the string or char opened by the quote at `start`, None
+    //for a quote that opens nothing such as that of a lifetime
+    fn quoted(line: &[char], start: usize) -> Option<usize> {
+        let quote = line[start];
+        let mut i = start + 1;
+        while i < line.len() {
+            if line[i] == '\\' {
+                i += 1;
+            } else if line[i] == quote {
+                return Some(i + 1);
+            }
+            i += 1;
+            if quote == '\'' && i > start + 3 {
+                return None;
+            }
+        }
+        if quote == '\'' {
+            None
+        } else {
+            Some(line.len())
+        }
+    }
+
+    fn span(&self, start: usize, end: usize, kind: TokenKind) -> Span {
+        let color = match kind {
+            TokenKind::Keyword => self.keyword_color,
+            TokenKind::String => self.string_color,
+            TokenKind::Number => self.number_color,
+            _ => self.comment_color,
+        };
+        Span {
+            start,
+            end,
+            kind,
+            color,
+        }
+    }
+}
+
+impl Tokenizer for SimpleTokenizer {
+    fn tokenize(&self, line: &[char], state: u32) -> (Vec<Span>, u32) {
+        let comment: Vec<char> = self.line_comment.chars().collect();
+        let (open, close): (Vec<char>, Vec<char>) = match self.block_comment {
+            Some((ref open, ref close)) => (open.chars().collect(), close.chars().collect()),
+            None => (vec![], vec![]),
+        };
+        let word = |c: char| c.is_alphanumeric() || c == '_';
+        let mut spans = vec![];
+        let mut i = 0;
+        //a block comment carried in from the line before
+        if state == IN_BLOCK_COMMENT && !close.is_empty() {
+            match find(line, &close, 0) {
+                Some(end) => {
+                    spans.push(self.span(0, end, TokenKind::Comment));
+                    i = end;
+                }
+                None => {
+                    spans.push(self.span(0, line.len(), TokenKind::Comment));
+                    return (spans, IN_BLOCK_COMMENT);
+                }
+            }
+        }
+        while i < line.len() {
+            let c = line[i];
+            let (end, kind) = if !comment.is_empty() && line[i..].starts_with(&comment) {
+                (line.len(), Some(TokenKind::Comment))
+            } else if !open.is_empty() && line[i..].starts_with(&open) {
+                match find(line, &close, i + open.len()) {
+                    Some(end) => (end, Some(TokenKind::Comment)),
+                    None => {
+                        spans.push(self.span(i, line.len(), TokenKind::Comment));
+                        return (spans, IN_BLOCK_COMMENT);
+                    }
+                }
+            } else if c == '"' || c == '\'' {
+                match SimpleTokenizer::quoted(line, i) {
+                    Some(end) => (end, Some(TokenKind::String)),
+                    None => (i + 1, None),
+                }
+            } else if word(c) {
+                let mut end = i;
+                while end < line.len() && (word(line[end]) || line[end] == '.') {
+                    //a dot only carries on a number
+                    if line[end] == '.' && !c.is_ascii_digit() {
+                        break;
+                    }
+                    end += 1;
+                }
+                let text: String = line[i..end].iter().collect();
+                if c.is_ascii_digit() {
+                    (end, Some(TokenKind::Number))
+                } else if self.keywords.contains(&text) {
+                    (end, Some(TokenKind::Keyword))
+                } else {
+                    (end, None)
+                }
+            } else {
+                (i + 1, None)
+            };
+            if let Some(kind) = kind {
+                spans.push(self.span(i, end, kind));
+            }
+            i = end;
+        }
+        (spans, 0)
+    }
+}
+
+//the offset just after the first `needle` in `line` from `from`
+fn find(line: &[char], needle: &[char], from: usize) -> Option<usize> {
+    (from..line.len())
+        .find(|i| line[*i..].starts_with(needle))
+        .map(|i| i + needle.len())
+}
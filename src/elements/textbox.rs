@@ -1,21 +1,75 @@
 use std::any::Any;
+use std::cell::Cell;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use clipboard::{ClipboardProvider};
+use glutin::VirtualKeyCode;
+
+use regex::Regex;
 
 use webrender::api::*;
 
 use crate::elements::element::*;
+use crate::elements::history::{Edit, EditKind, History};
+use crate::elements::layout::*;
+use crate::elements::scrollbox;
+use crate::elements::validation::{CharFilter, Mask};
+use crate::gui::clipboard;
 use crate::gui::font;
 use crate::gui::properties;
 use crate::gui::properties::Position;
+use crate::gui::style;
+use crate::gui::window;
+
+//edits that can be undone unless set_history_depth says otherwise
+const HISTORY_DEPTH: usize = 100;
+
+//how long the caret shows and then hides for unless set_blink_rate says
+//otherwise
+const BLINK_RATE: Duration = Duration::from_millis(530);
+
+//presses closer together than this select a word and then a line
+const MULTI_CLICK: Duration = Duration::from_millis(400);
+
+//how often dragging past the end of a single line selects a char further
+const DRAG_SCROLL: Duration = Duration::from_millis(50);
 
+//What a TextBox passes to its Changed handler before the value changes.
+//The handler can call `cancel` to keep the old value.
+#[derive(Debug)]
+pub struct TextChange {
+    pub old: String,
+    pub new: String,
+    cancelled: Cell<bool>,
+}
+
+impl TextChange {
+    pub fn new(old: String, new: String) -> TextChange {
+        TextChange {
+            old,
+            new,
+            cancelled: Cell::new(false),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.set(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.get()
+    }
+}
+
+//Offsets are between chars, 0 is before the first one. The selection
+//runs from `anchor` to the caret, which is where it was extended to.
 pub struct TextBox {
     ext_id: u64,
     value: Vec<char>,
     placeholder: Vec<char>,
     props: properties::Properties,
     bounds: properties::Extent,
+    text_bounds: properties::Extent,
     focus: bool,
     event_handlers: EventHandlers,
     drawn: u8,
@@ -29,13 +83,30 @@ pub struct TextBox {
     is_password: bool,
     cache: font::Paragraphs,
     selecting: bool,
+    click: ClickState,
+    anchor: usize,
+    last_press: Option<Instant>,
+    presses: u8,
+    preferred_x: Option<f32>, //kept by moves up and down
+    scroll_x: f32,            //how far a single line is moved sideways
+    drag_at: Option<Position>,
+    drag_step: Instant,
+    history: History,
+    blink_rate: Duration,
+    blink_start: Instant,
+    caret_moved: Option<(usize, bool, usize)>, //the caret shows again when it moves
+    max_length: Option<usize>,
+    filter: Option<CharFilter>,
+    mask: Option<Mask>,
+    pattern: Option<Regex>,
+    valid: bool,
 }
 
 impl TextBox {
     pub fn new(s: String) -> Self {
         let mut props = properties::Properties::new();
         props.default();
-        props.set(properties::Property::Height(properties::Unit::Natural));
+        props.set_default(properties::Property::Height(properties::Unit::Natural));
         TextBox {
             ext_id: 0,
             value: s.chars().collect(),
@@ -48,6 +119,7 @@ impl TextBox {
                 h: 0.0,
                 dpi: 0.0,
             },
+            text_bounds: properties::Extent::new(),
             focus: false,
             event_handlers: EventHandlers::new(),
             drawn: 0,
@@ -61,18 +133,38 @@ impl TextBox {
             is_password: false,
             cache: font::Paragraphs::new(),
             selecting: false,
+            click: ClickState::default(),
+            anchor: 0,
+            last_press: None,
+            presses: 0,
+            preferred_x: None,
+            scroll_x: 0.,
+            drag_at: None,
+            drag_step: Instant::now(),
+            history: History::new(HISTORY_DEPTH),
+            blink_rate: BLINK_RATE,
+            blink_start: Instant::now(),
+            caret_moved: None,
+            max_length: None,
+            filter: None,
+            mask: None,
+            pattern: None,
+            valid: true,
         }
     }
 
+    //Replaces the text, which can be undone until clear_history. Like
+    //every other change it goes to the Changed handler first.
     pub fn set_value(&mut self, s: String) {
-        self.value = s.chars().collect();
-        self.drawn = 0;
+        let len = self.value.len();
+        self.edit(EditKind::Replace, 0, len, s.chars().collect());
     }
 
     pub fn append_value(&mut self, s: &str) {
-        let mut s: Vec<char> = s.chars().collect();
-        self.value.append(&mut s);
-        self.drawn = 0;
+        let len = self.value.len();
+        let selection = self.get_selection();
+        self.edit(EditKind::Insert, len, len, s.chars().collect());
+        self.set_selection(selection.0, selection.1);
     }
 
     pub fn get_value(&self) -> String {
@@ -80,11 +172,9 @@ impl TextBox {
     }
 
     pub fn set_editable(&mut self, editable: bool) {
+        //a box that can not be edited can still be selected and copied from
         self.editable = editable;
         self.drawn = 0;
-        if !editable {
-            self.focus = false;
-        }
     }
 
     pub fn get_editable(&self) -> bool {
@@ -107,7 +197,8 @@ impl TextBox {
                 let pos = ch.get_position();
                 let adv = ch.get_metric().advance;
                 let mut after = false;
-                if p.x > pos.x + (adv.x/2.) {
+                //the right half of a right to left char comes before it
+                if (p.x > pos.x + (adv.x/2.)) != ch.get_rtl() {
                     //ind += 1;
                     after = true;
                 }
@@ -116,6 +207,127 @@ impl TextBox {
         }
     }
 
+    //the offsets of the anchor and the caret
+    pub fn get_selection(&self) -> (usize, usize) {
+        (self.anchor.min(self.value.len()), self.caret())
+    }
+
+    //selects from `anchor` to `focus` and puts the caret at `focus`
+    pub fn set_selection(&mut self, anchor: usize, focus: usize) {
+        self.anchor = anchor.min(self.value.len());
+        self.set_caret(focus);
+    }
+
+    pub fn select_all(&mut self) {
+        let len = self.value.len();
+        self.set_selection(0, len);
+    }
+
+    pub fn get_selected_text(&self) -> String {
+        let (start, end) = self.selected();
+        self.value[start..end].iter().collect()
+    }
+
+    //reverts the last edit, false if there is none or it was cancelled
+    pub fn undo(&mut self) -> bool {
+        match self.history.undo() {
+            Some(edit) => {
+                if !self.apply(&edit.inserted, &edit.removed, edit.offset) {
+                    self.history.redo();
+                    return false;
+                }
+                self.set_selection(edit.before.0, edit.before.1);
+                true
+            }
+            None => false,
+        }
+    }
+
+    //makes the last undone edit again, false if there is none or it was
+    //cancelled
+    pub fn redo(&mut self) -> bool {
+        match self.history.redo() {
+            Some(edit) => {
+                if !self.apply(&edit.removed, &edit.inserted, edit.offset) {
+                    self.history.undo();
+                    return false;
+                }
+                self.set_selection(edit.after.0, edit.after.1);
+                true
+            }
+            None => false,
+        }
+    }
+
+    //forgets every edit, such as after loading a new value
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    pub fn set_history_depth(&mut self, depth: usize) {
+        self.history.set_depth(depth);
+    }
+
+    pub fn get_history_depth(&self) -> usize {
+        self.history.get_depth()
+    }
+
+    //how long the caret shows and then hides for, zero keeps it shown
+    pub fn set_blink_rate(&mut self, rate: Duration) {
+        self.blink_rate = rate;
+    }
+
+    pub fn get_blink_rate(&self) -> Duration {
+        self.blink_rate
+    }
+
+    //at most `max` chars can be typed or pasted
+    pub fn set_max_length(&mut self, max: Option<usize>) {
+        self.max_length = max;
+        self.update_validity();
+    }
+
+    pub fn get_max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+
+    //typed and pasted chars the filter does not allow are left out
+    pub fn set_filter(&mut self, filter: Option<CharFilter>) {
+        self.filter = filter;
+        self.update_validity();
+    }
+
+    pub fn get_filter(&self) -> Option<CharFilter> {
+        self.filter.clone()
+    }
+
+    //what is typed is fitted into the mask, the value is valid once it
+    //is filled
+    pub fn set_mask(&mut self, mask: Option<Mask>) {
+        self.mask = mask;
+        self.update_validity();
+    }
+
+    pub fn get_mask(&self) -> Option<Mask> {
+        self.mask.clone()
+    }
+
+    //the whole value has to match `pattern` to be valid
+    pub fn set_pattern(&mut self, pattern: Option<&str>) -> Result<(), regex::Error> {
+        self.pattern = match pattern {
+            Some(p) => Some(Regex::new(&format!("^(?:{})$", p))?),
+            None => None,
+        };
+        self.update_validity();
+        Ok(())
+    }
+
+    //Whether the value keeps to the max length, filter, mask and pattern.
+    //A value set from code can break them, what is typed can not.
+    pub fn is_valid(&self) -> bool {
+        self.valid
+    }
+
     pub fn set_placeholder(&mut self, p: String) {
         self.placeholder = p.chars().collect();
     }
@@ -124,6 +336,428 @@ impl TextBox {
         self.placeholder.clone().iter().collect()
     }
 
+    //states stylesheet selectors can match
+    fn states(&self) -> Vec<style::State> {
+        let mut states = vec![];
+        if self.hovering {
+            states.push(style::State::Hover);
+        }
+        if self.focus && self.editable {
+            states.push(style::State::Focus);
+        }
+        if !self.enabled {
+            states.push(style::State::Disabled);
+        }
+        if !self.valid {
+            states.push(style::State::Invalid);
+        }
+        states
+    }
+
+    fn caret(&self) -> usize {
+        let len = self.value.len();
+        if self.cursor_after && len > 0 {
+            (self.cursor_index + 1).min(len)
+        } else {
+            self.cursor_index.min(len)
+        }
+    }
+
+    //the caret is drawn before the char at the offset, or after the last
+    fn caret_at(&self, offset: usize) -> (usize, bool) {
+        let len = self.value.len();
+        let offset = offset.min(len);
+        if offset == len && len > 0 {
+            (len - 1, true)
+        } else {
+            (offset, false)
+        }
+    }
+
+    fn set_caret(&mut self, offset: usize) {
+        let (index, after) = self.caret_at(offset);
+        self.cursor_index = index;
+        self.cursor_after = after;
+        self.preferred_x = None;
+    }
+
+    //Moves the caret before or after the char at `index`, which tells
+    //apart the end of a wrapped line from the start of the next one.
+    fn place_caret(&mut self, (index, after): (usize, bool), extend: bool) {
+        self.cursor_index = index;
+        self.cursor_after = after;
+        if !extend {
+            self.anchor = self.caret();
+        }
+    }
+
+    //moves the caret, extending the selection or collapsing it
+    fn move_caret(&mut self, offset: usize, extend: bool) {
+        self.set_caret(offset);
+        if !extend {
+            self.anchor = self.caret();
+        }
+    }
+
+    //the selected offsets, start first
+    fn selected(&self) -> (usize, usize) {
+        let (anchor, caret) = self.get_selection();
+        (anchor.min(caret), anchor.max(caret))
+    }
+
+    //Replaces the chars from `start` to `end` with `text` and puts the
+    //caret after it. Every change to the value goes through here so it
+    //can be undone.
+    fn edit(&mut self, kind: EditKind, start: usize, end: usize, text: Vec<char>) -> bool {
+        let caret = start + text.len();
+        self.edit_to(kind, start, end, text, caret)
+    }
+
+    //edit that leaves the caret at `caret`, false if it was cancelled
+    fn edit_to(
+        &mut self,
+        kind: EditKind,
+        start: usize,
+        end: usize,
+        text: Vec<char>,
+        caret: usize,
+    ) -> bool {
+        let before = self.get_selection();
+        let removed: Vec<char> = self.value[start..end].to_vec();
+        if !self.apply(&removed, &text, start) {
+            return false;
+        }
+        self.move_caret(caret, false);
+        self.history.record(Edit {
+            kind,
+            offset: start,
+            removed,
+            inserted: text,
+            before,
+            after: self.get_selection(),
+        });
+        true
+    }
+
+    //Swaps the chars `old` at `offset` for `new` unless the Changed
+    //handler cancels it, in which case it is false.
+    fn apply(&mut self, old: &[char], new: &[char], offset: usize) -> bool {
+        let offset = offset.min(self.value.len());
+        let end = (offset + old.len()).min(self.value.len());
+        let mut value = self.value.clone();
+        value.splice(offset..end, new.iter().cloned());
+        let change = TextChange::new(self.get_value(), value.iter().collect());
+        self.exec_handler(ElementEvent::Changed, &change);
+        if change.is_cancelled() {
+            return false;
+        }
+        self.value = value;
+        self.drawn = 0;
+        self.update_validity();
+        true
+    }
+
+    //Edits made by typing, pasting and deleting, which the constraints
+    //can turn down before they reach the value. Chars the filter does not
+    //allow are left out and a paste is cut at the max length. A single
+    //line gets spaces for line breaks. False if nothing could be put in.
+    fn input(&mut self, kind: EditKind, start: usize, end: usize, text: Vec<char>) -> bool {
+        let mut text = text;
+        if self.singleline {
+            for c in text.iter_mut().filter(|c| **c == '\n') {
+                *c = ' ';
+            }
+        }
+        if let Some(ref filter) = self.filter {
+            text.retain(|c| filter.allows(*c));
+        }
+        if let Some(max) = self.max_length {
+            let room = max.saturating_sub(self.value.len() - (end - start));
+            text.truncate(room);
+        }
+        if start == end && text.is_empty() {
+            return false;
+        }
+
+        let mut value = self.value[..start].to_vec();
+        value.extend(text.iter().cloned());
+        value.extend(self.value[end..].iter().cloned());
+        let mut caret = start + text.len();
+        if let Some(ref mask) = self.mask {
+            match mask.apply(&value, caret) {
+                Some((masked, at)) => {
+                    value = masked;
+                    caret = at;
+                }
+                None => return false,
+            }
+        }
+        if let Some(max) = self.max_length {
+            if value.len() > max && value.len() > self.value.len() {
+                return false;
+            }
+        }
+
+        //a mask can put back what was deleted, a literal for one
+        if value == self.value {
+            self.move_caret(caret, false);
+            return true;
+        }
+
+        //only what changed is recorded, a mask can change more than what
+        //was typed over
+        let len = self.value.len();
+        let prefix = self
+            .value
+            .iter()
+            .zip(value.iter())
+            .take_while(|(a, b)| a == b)
+            .count()
+            .min(start);
+        let suffix = self.value[prefix..]
+            .iter()
+            .rev()
+            .zip(value[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count()
+            .min(len - end);
+        let inserted = value[prefix..value.len() - suffix].to_vec();
+        self.edit_to(kind, prefix, len - suffix, inserted, caret)
+    }
+
+    //checks the value against the constraints and tells the handler of
+    //ValidityChange when that changes the outcome
+    fn update_validity(&mut self) {
+        let value = &self.value;
+        let mut valid = match self.max_length {
+            Some(max) => value.len() <= max,
+            None => true,
+        };
+        if let Some(ref filter) = self.filter {
+            valid = valid && value.iter().all(|c| filter.allows(*c));
+        }
+        if let Some(ref mask) = self.mask {
+            valid = valid && (value.is_empty() || mask.is_complete(value));
+        }
+        if let Some(ref pattern) = self.pattern {
+            let text: String = value.iter().collect();
+            valid = valid && pattern.is_match(&text);
+        }
+        if valid != self.valid {
+            self.valid = valid;
+            self.drawn = 0;
+            self.exec_handler(ElementEvent::ValidityChange, &valid);
+        }
+    }
+
+    //removes the selected chars, false if there were none
+    fn delete_selection(&mut self) -> bool {
+        let (start, end) = self.selected();
+        if start == end {
+            return false;
+        }
+        self.input(EditKind::Delete, start, end, vec![]);
+        true
+    }
+
+    //pastes `text` at the caret in place of the selection
+    fn insert_text(&mut self, text: &str) {
+        let (start, end) = self.selected();
+        let text: Vec<char> = text.chars().filter(|c| *c != '\r').collect();
+        self.input(EditKind::Paste, start, end, text);
+    }
+
+    //what is copied, nothing out of a password box
+    fn copyable(&self) -> Option<String> {
+        let (start, end) = self.selected();
+        if start == end || self.is_password {
+            None
+        } else {
+            Some(self.get_selected_text())
+        }
+    }
+
+    //X11 pastes what was selected last with a middle click. Called when a
+    //selecting click, drag or shift+arrows ends rather than on every step.
+    fn publish_selection(&self) {
+        if let Some(text) = self.copyable() {
+            clipboard::set_primary_text(text);
+        }
+    }
+
+    //the run of chars of the same kind as the one at `index`
+    fn word_at(&self, index: usize) -> (usize, usize) {
+        let len = self.value.len();
+        if len == 0 {
+            return (0, 0);
+        }
+        let index = index.min(len - 1);
+        let kind = CharKind::of(self.value[index]);
+        let mut start = index;
+        while start > 0 && CharKind::of(self.value[start - 1]) == kind {
+            start -= 1;
+        }
+        let mut end = index + 1;
+        while end < len && CharKind::of(self.value[end]) == kind {
+            end += 1;
+        }
+        (start, end)
+    }
+
+    //the line the char at `index` is on, without its line break
+    fn line_at(&self, index: usize) -> (usize, usize) {
+        let len = self.value.len();
+        let index = index.min(len);
+        let start = self.value[..index]
+            .iter()
+            .rposition(|c| *c == '\n')
+            .map_or(0, |i| i + 1);
+        let end = self.value[index..]
+            .iter()
+            .position(|c| *c == '\n')
+            .map_or(len, |i| index + i);
+        (start, end)
+    }
+
+    //the start of the word after the caret
+    fn next_word(&self, offset: usize) -> usize {
+        let len = self.value.len();
+        let mut i = offset.min(len);
+        if i < len {
+            let kind = CharKind::of(self.value[i]);
+            while kind != CharKind::Space && i < len && CharKind::of(self.value[i]) == kind {
+                i += 1;
+            }
+        }
+        while i < len && CharKind::of(self.value[i]) == CharKind::Space {
+            i += 1;
+        }
+        i
+    }
+
+    //the start of the word before the caret
+    fn previous_word(&self, offset: usize) -> usize {
+        let mut i = offset.min(self.value.len());
+        while i > 0 && CharKind::of(self.value[i - 1]) == CharKind::Space {
+            i -= 1;
+        }
+        if i > 0 {
+            let kind = CharKind::of(self.value[i - 1]);
+            while i > 0 && CharKind::of(self.value[i - 1]) == kind {
+                i -= 1;
+            }
+        }
+        i
+    }
+
+    //the start of the paragraph the caret is in, or of the one after it
+    fn paragraph(&self, down: bool) -> usize {
+        let len = self.value.len();
+        let caret = self.caret();
+        let (start, end) = self.line_at(caret);
+        if down {
+            (end + 1).min(len)
+        } else if caret > start || start == 0 {
+            start
+        } else {
+            self.line_at(start - 1).0
+        }
+    }
+
+    //x of the caret, None before its text is shaped
+    fn caret_x(&self) -> Option<f32> {
+        let ch = self.cache.get_char_at_index(self.cursor_index)?;
+        Some(ch.get_caret_x(self.cursor_after))
+    }
+
+    //The caret stop next to the caret on its line, in the order the line
+    //is shown in. Past either end it goes on to the next or the previous
+    //line in the text.
+    fn horizontal(&self, right: bool) -> Option<(usize, bool)> {
+        let x = self.caret_x()?;
+        let (stops, rtl) = self.cache.get_caret_stops(self.cursor_index)?;
+        let caret = self.caret();
+        let ahead: Vec<&font::CaretStop> = stops
+            .iter()
+            .filter(|s| if right { s.x > x + 0.5 } else { s.x < x - 0.5 })
+            .collect();
+        let nearest = if right { ahead.first() } else { ahead.last() };
+        if let Some(nearest) = nearest {
+            //where directions meet stops share an x, take the closest in the text
+            let stop = ahead
+                .iter()
+                .filter(|s| (s.x - nearest.x).abs() <= 0.5)
+                .min_by_key(|s| {
+                    let offset = s.index + s.after as usize;
+                    offset.max(caret) - offset.min(caret)
+                })?;
+            return Some((stop.index, stop.after));
+        }
+
+        let len = self.value.len();
+        if right != rtl {
+            if self.cursor_after && caret < len {
+                Some((caret, false))
+            } else if caret < len {
+                Some(self.caret_at(caret + 1))
+            } else {
+                None
+            }
+        } else if !self.cursor_after && caret > 0 && self.value[caret - 1] != '\n' {
+            Some((caret - 1, true))
+        } else if caret > 0 {
+            Some(self.caret_at(caret - 1))
+        } else {
+            None
+        }
+    }
+
+    //the stop nearest to `x` `lines` lines down, or up when negative,
+    //and the start or the end of the text past the first or last line
+    fn vertical(&self, x: f32, lines: f32) -> Option<(usize, bool)> {
+        let ch = self.cache.get_char_at_index(self.cursor_index)?;
+        let size = ch.get_metric().size;
+        let p = Position {
+            x,
+            y: ch.get_position().y + (lines - 0.5) * size,
+        };
+        let target = self.cache.get_char_at_pos(&p, &self.value)?;
+        let line = self.cache.get_line_range(self.cursor_index);
+        if self.cache.get_line_range(target.get_index()) == line {
+            return Some(if lines < 0. {
+                (0, false)
+            } else {
+                self.caret_at(self.value.len())
+            });
+        }
+        let pos = target.get_position();
+        let right_half = p.x > pos.x + target.get_metric().advance.x / 2.;
+        let after = target.get_char() != '\n' && right_half != target.get_rtl();
+        Some((target.get_index(), after))
+    }
+
+    //the lines that fit in the box, what page up and down move by
+    fn page_lines(&self) -> f32 {
+        match self.cache.get_char_at_index(self.cursor_index) {
+            Some(ref ch) if ch.get_metric().size > 0. => {
+                (self.bounds.h / ch.get_metric().size).floor().max(1.)
+            }
+            _ => 1.,
+        }
+    }
+
+    //the start or the end of the line the caret is shown on
+    fn line_edge(&self, end: bool) -> Option<(usize, bool)> {
+        let (first, last) = self.cache.get_line_range(self.cursor_index)?;
+        if !end {
+            Some((first, false))
+        } else if self.value.get(last) == Some(&'\n') {
+            Some((last, false))
+        } else {
+            Some((last, true))
+        }
+    }
+
     fn set_cursor(&mut self, p: &Position){
         let tmp = self.cache.get_char_at_pos(&p, &self.value);
         if tmp.is_some() {
@@ -131,6 +765,7 @@ impl TextBox {
             let tmp = self.get_cursor_index();
             self.cursor_index = tmp.0;
             self.cursor_after = tmp.1;
+            self.preferred_x = None;
             //println!("Clicked at ind[{}] {:?} ... appears after? {}", self.cursor_index, self.cursor, self.cursor_after);
         }
     }
@@ -141,6 +776,10 @@ impl Element for TextBox {
         self.ext_id
     }
 
+    fn get_kind(&self) -> &'static str {
+        "TextBox"
+    }
+
     fn set(&mut self, prop: properties::Property) {
         self.props.set(prop);
     }
@@ -165,31 +804,54 @@ impl Element for TextBox {
         let _id = gen.get();
         self.ext_id = _id;
 
-        let size = self.props.get_size() as f32;
-        let family = self.props.get_family();
-        let mut color = self.props.get_color();
-        let mut bgcolor = self.props.get_bg_color();
-        let width = self.props.get_width();
-        let height = self.props.get_height();
-        let text_align = self.props.get_text_align();
+        let props = style::computed("TextBox", &self.props, &_props, self.states())
+            .resolved(extent.w, extent.h);
+        let size = props.get_size() as f32;
+        let family = props.get_family();
+        let mut color = props.get_color();
+        let mut bgcolor = props.get_bg_color();
+        let width = props.get_width();
+        let height = props.get_height();
+        let text_align = props.get_text_align();
 
         if self.hovering {
-            color = self.props.get_hover_color();
-            bgcolor = self.props.get_hover_bg_color();
+            color = props.get_hover_color();
+            bgcolor = props.get_hover_bg_color();
         }
 
         if self.focus && self.editable {
-            color = self.props.get_focus_color();
-            bgcolor = self.props.get_focus_bg_color();
+            color = props.get_focus_color();
+            bgcolor = props.get_focus_bg_color();
+        }
+
+        if !self.valid {
+            color = props.get_invalid_color();
+            bgcolor = props.get_invalid_bg_color();
         }
 
         if !self.enabled {
-            color = self.props.get_disabled_color();
-            bgcolor = self.props.get_disabled_bg_color();
+            color = props.get_disabled_color();
+            bgcolor = props.get_disabled_bg_color();
         }
 
         if self.value.is_empty() && !self.placeholder.is_empty() && !self.focus && !self.hovering {
-            color = self.props.get_disabled_color();
+            color = props.get_disabled_color();
+        }
+
+        //dragging past either end of a single line goes on selecting what
+        //is scrolled out of sight
+        let dragged = self.drag_at.as_ref().map(|p| p.x);
+        if let (true, true, Some(x)) = (self.selecting, self.singleline, dragged) {
+            let right = x > self.bounds.x + self.bounds.w;
+            if right || x < self.bounds.x {
+                if self.drag_step.elapsed() >= DRAG_SCROLL {
+                    if let Some(stop) = self.horizontal(right) {
+                        self.place_caret(stop, true);
+                    }
+                    self.drag_step = Instant::now();
+                }
+                window::wake_at(self.drag_step + DRAG_SCROLL);
+            }
         }
 
         let (_f_key, fi_key) = font_store.get_font_instance(&family, size as i32);
@@ -218,10 +880,27 @@ impl Element for TextBox {
             None => size,
         };
 
+        //the text is placed with its height from the last render
+        let box_h = element_size(
+            &height,
+            &props.get_min_height(),
+            &props.get_max_height(),
+            extent.h,
+            self.text_bounds.h,
+        );
+        let text_y = text_top(
+            &props.get_vertical_align(),
+            extent.y,
+            box_h,
+            self.text_bounds.h,
+            baseline,
+        );
+
         let mut paras = font::Paragraphs::from_chars(value);
+        paras.set_wrap(!self.singleline);
         paras.shape(
             extent.x,
-            extent.y,
+            text_y,
             extent.w,
             extent.h,
             size,
@@ -230,11 +909,7 @@ impl Element for TextBox {
             &text_align,
         );
         let _bounds = paras.get_extent();
-        let glyphs = paras.glyphs();
-
-        if !self.value.is_empty() {
-            self.cache = paras;
-        }
+        self.text_bounds = _bounds.clone();
 
         /*let (mut cursor_x, mut cursor_y, cursor_i) = (0.0, 0.0, self.cursor);
 
@@ -249,22 +924,20 @@ impl Element for TextBox {
             cursor_y = (self.cache[cursor_i - 1].1).1;
         }*/
 
-        let mut calc_w = _bounds.w;
-        let mut calc_h = _bounds.h;
-
-        calc_w = match width {
-            properties::Unit::Extent => extent.w,
-            properties::Unit::Pixel(px) => px,
-            properties::Unit::Stretch(s) => s * extent.w,
-            properties::Unit::Natural => calc_w,
-        };
-
-        calc_h = match height {
-            properties::Unit::Extent => extent.h,
-            properties::Unit::Pixel(px) => px,
-            properties::Unit::Stretch(s) => s * extent.h,
-            properties::Unit::Natural => calc_h,
-        };
+        let calc_w = element_size(
+            &width,
+            &props.get_min_width(),
+            &props.get_max_width(),
+            extent.w,
+            _bounds.w,
+        );
+        let calc_h = element_size(
+            &height,
+            &props.get_min_height(),
+            &props.get_max_height(),
+            extent.h,
+            _bounds.h,
+        );
 
         self.bounds = properties::Extent {
             x: extent.x,
@@ -274,39 +947,114 @@ impl Element for TextBox {
             dpi: extent.dpi,
         };
 
-        let mut info = LayoutPrimitiveInfo::new(LayoutRect::new(
+        //a single line longer than the box is moved sideways to keep the
+        //caret in it
+        let caret_w = props.get_caret_width();
+        if self.singleline && _bounds.w > self.bounds.w {
+            let mut shift = self.scroll_x;
+            if self.focus {
+                if let Some((x, _)) = caret_spot(&paras, self.cursor_index, self.cursor_after, size) {
+                    let right = extent.x + self.bounds.w - caret_w;
+                    if x + shift < extent.x {
+                        shift = extent.x - x;
+                    } else if x + shift > right {
+                        shift = right - x;
+                    }
+                }
+            }
+            let min = extent.x + self.bounds.w - (_bounds.x + _bounds.w);
+            shift = shift.max(min).min(extent.x - _bounds.x);
+            self.scroll_x = shift;
+            paras.position(extent.x + shift, text_y, extent.w, extent.h, size, &text_align);
+        } else {
+            self.scroll_x = 0.;
+        }
+        let glyphs = paras.glyphs();
+
+        if !self.value.is_empty() {
+            self.cache = paras;
+        } else {
+            //the placeholder can not be clicked into or selected
+            self.cache = font::Paragraphs::new();
+        }
+
+        let clip = LayoutRect::new(
             LayoutPoint::new(extent.x, extent.y),
             LayoutSize::new(self.bounds.w, self.bounds.h),
-        ));
+        );
+        let mut info = LayoutPrimitiveInfo::new(clip);
         info.tag = Some((_id, 0));
         builder.push_rect(&info, bgcolor);
 
-        let info = LayoutPrimitiveInfo::new(LayoutRect::new(
-            LayoutPoint::new(extent.x, extent.y),
-            LayoutSize::new(self.bounds.w, self.bounds.h),
-        ));
+        //the selection goes behind the text
+        let (start, end) = self.selected();
+        if start < end {
+            let selection_color = props.get_selection_bg_color();
+            for r in self.cache.get_selection_rects(start, end) {
+                let info = LayoutPrimitiveInfo::with_clip_rect(
+                    LayoutRect::new(LayoutPoint::new(r.x, r.y), LayoutSize::new(r.w, r.h)),
+                    clip,
+                );
+                builder.push_rect(&info, selection_color);
+            }
+        }
+
+        //the text is clipped to the box
+        let info = LayoutPrimitiveInfo::new(clip);
         builder.push_text(&info, &glyphs, fi_key, color, Some(GlyphOptions::default()));
 
-        //add the cursor
+        //add the cursor, it blinks from when it last moved
         if self.focus && self.enabled && self.editable {
-            let ch = self.cache.get_char_at_index(self.cursor_index);
-            match ch {
-                Some(ref ch) => {
-                    let pos = ch.get_position();
-                    let mut info = LayoutPrimitiveInfo::new(LayoutRect::new(
-                        LayoutPoint::new(pos.x, pos.y - size),
-                        LayoutSize::new(1.0, size),
-                    ));
-                    if self.cursor_after {
-                        info = LayoutPrimitiveInfo::new(LayoutRect::new(
-                            LayoutPoint::new(pos.x + ch.get_metric().advance.x, pos.y - size),
-                            LayoutSize::new(1.0, size),
-                        ));
-                    }
-                    builder.push_rect(&info, color);
-                },
-                None => (),
+            let moved = (self.cursor_index, self.cursor_after, self.value.len());
+            let caret_moved = self.caret_moved != Some(moved);
+            if caret_moved {
+                self.caret_moved = Some(moved);
+                self.blink_start = Instant::now();
+            }
+            let shown = if self.blink_rate == Duration::from_millis(0) {
+                true
+            } else {
+                let phase = self.blink_start.elapsed().as_millis() / self.blink_rate.as_millis();
+                window::wake_at(self.blink_start + self.blink_rate * (phase as u32 + 1));
+                phase % 2 == 0
+            };
+
+            let caret = caret_spot(&self.cache, self.cursor_index, self.cursor_after, size);
+            //an empty box has it where its text would start
+            let caret = caret.or_else(|| {
+                if !self.value.is_empty() {
+                    return None;
+                }
+                let x = match text_align {
+                    properties::Align::Middle => extent.x + self.bounds.w / 2.,
+                    properties::Align::Right => extent.x + self.bounds.w - caret_w,
+                    _ => extent.x,
+                };
+                Some((x, text_y + baseline - size))
+            });
+            if let Some((x, y)) = caret {
+                //the candidates of an input method go under the caret
+                window::set_ime_spot(x, y + size);
+                //and a ScrollBox the box is in shows where it moved to
+                if caret_moved {
+                    scrollbox::reveal(properties::Extent {
+                        x,
+                        y,
+                        w: caret_w,
+                        h: size,
+                        dpi: extent.dpi,
+                    });
+                }
+            }
+            if let (true, Some((x, y))) = (shown, caret) {
+                let info = LayoutPrimitiveInfo::with_clip_rect(
+                    LayoutRect::new(LayoutPoint::new(x, y), LayoutSize::new(caret_w, size)),
+                    clip,
+                );
+                builder.push_rect(&info, props.get_caret_color());
             }
+        } else {
+            self.caret_moved = None;
         }
     }
 
@@ -316,66 +1064,108 @@ impl Element for TextBox {
 
     fn on_primitive_event(&mut self, ext_ids: &[ItemTag], e: PrimitiveEvent) -> bool {
         let mut handled = false;
+        //only a release after a press on the box clicks it
+        let over = !ext_ids.is_empty() && ext_ids[0].0 == self.ext_id;
+        let clicked = self.click.update(self.ext_id, over, &e);
         match e {
-            PrimitiveEvent::KeyInput(vkc, _sc, _s, _m) => match vkc {
-                _ => (),
+            PrimitiveEvent::KeyInput(vkc, _sc, s, m) => {
+                if self.focus && self.enabled && s == properties::ButtonState::Pressed {
+                    let (start, end) = self.selected();
+                    let collapse = !m.shift && start < end;
+                    let caret = self.caret();
+                    let len = self.value.len();
+                    //up and down keep to the column they started from
+                    let x = self.preferred_x.or_else(|| self.caret_x());
+                    let page = self.page_lines();
+                    let vertical = |lines: f32| x.and_then(|x| self.vertical(x, lines));
+                    let target = match vkc {
+                        Some(VirtualKeyCode::Left) if collapse => Some(self.caret_at(start)),
+                        Some(VirtualKeyCode::Right) if collapse => Some(self.caret_at(end)),
+                        Some(VirtualKeyCode::Left) if m.ctrl => Some(self.caret_at(self.previous_word(caret))),
+                        Some(VirtualKeyCode::Right) if m.ctrl => Some(self.caret_at(self.next_word(caret))),
+                        Some(VirtualKeyCode::Left) => self.horizontal(false),
+                        Some(VirtualKeyCode::Right) => self.horizontal(true),
+                        Some(VirtualKeyCode::Up) if m.ctrl => Some(self.caret_at(self.paragraph(false))),
+                        Some(VirtualKeyCode::Down) if m.ctrl => Some(self.caret_at(self.paragraph(true))),
+                        Some(VirtualKeyCode::Up) => vertical(-1.),
+                        Some(VirtualKeyCode::Down) => vertical(1.),
+                        Some(VirtualKeyCode::PageUp) => vertical(-page),
+                        Some(VirtualKeyCode::PageDown) => vertical(page),
+                        Some(VirtualKeyCode::Home) if m.ctrl => Some(self.caret_at(0)),
+                        Some(VirtualKeyCode::End) if m.ctrl => Some(self.caret_at(len)),
+                        Some(VirtualKeyCode::Home) => self.line_edge(false),
+                        Some(VirtualKeyCode::End) => self.line_edge(true),
+                        _ => None,
+                    };
+                    let keeps_column = match vkc {
+                        Some(VirtualKeyCode::Up) | Some(VirtualKeyCode::Down) => !m.ctrl,
+                        Some(VirtualKeyCode::PageUp) | Some(VirtualKeyCode::PageDown) => true,
+                        _ => false,
+                    };
+                    if let Some(target) = target {
+                        self.place_caret(target, m.shift);
+                        self.preferred_x = if keeps_column { x } else { None };
+                        handled = true;
+                    }
+                } else if self.focus
+                && s == properties::ButtonState::Released
+                && (vkc == Some(VirtualKeyCode::LShift) || vkc == Some(VirtualKeyCode::RShift))
+                {
+                    //selecting with shift+arrows ends when shift is let go
+                    self.publish_selection();
+                }
             },
             PrimitiveEvent::Char(mut c) => {
-                if self.focus && self.enabled && self.editable {
-                    if c == '\x08' { //backspace
-                        let len = self.value.len();
-                        //if len > 0 and after is true then should delete from index
-                        if len > 0 && self.cursor_after {
-                            self.value.remove(self.cursor_index);
-                            if self.cursor_index == 0 {
-                                self.cursor_after = false;
-                            } else {
-                                self.cursor_index -= 1;
-                            }
-                        }
-                        // if len > 0 and after is false
-                        else if len > 0 && !self.cursor_after {
-                            if self.cursor_index > 0 {
-                                self.value.remove(self.cursor_index - 1);
-                                self.cursor_index -= 1;
-                            }
+                if self.focus && self.enabled && c == '\u{1}' { //select all
+                    self.select_all();
+                    self.publish_selection();
+                    handled = true;
+                } else if self.focus && self.enabled && c == '\u{3}' { //copy
+                    if let Some(text) = self.copyable() {
+                        clipboard::set_text(text);
+                    }
+                    handled = true;
+                } else if self.focus && self.enabled && self.editable {
+                    self.preferred_x = None;
+                    if (c == '\x08' || c == '\u{7f}') && self.delete_selection() {
+                        //backspace and delete remove the selection
+                    } else if c == '\x08' { //backspace
+                        let caret = self.caret();
+                        if caret > 0 {
+                            self.input(EditKind::Delete, caret - 1, caret, vec![]);
                         }
                     } else if c == '\u{7f}' { //delete key
-                        let len = self.value.len();
-                        if len > self.cursor_index+1 && self.cursor_after {
-                            self.value.remove(self.cursor_index+1);
+                        let caret = self.caret();
+                        if caret < self.value.len() {
+                            self.input(EditKind::Delete, caret, caret + 1, vec![]);
                         }
-                        else if len > self.cursor_index && !self.cursor_after{
-                            self.value.remove(self.cursor_index);
-                            let len = self.value.len();
-                            if self.cursor_index > 0 && self.cursor_index == len && len > 0 {
-                                self.cursor_index -=1;
-                                self.cursor_after = true;
-                            }
+                    } else if c == '\u{18}' { //cut
+                        if let Some(text) = self.copyable() {
+                            clipboard::set_text(text);
+                            self.delete_selection();
                         }
-
-                    } else if c == '\u{3}' {
-
-                    } else if c == '\u{16}' {
-
+                    } else if c == '\u{16}' { //paste
+                        if let Some(text) = clipboard::get_text() {
+                            self.insert_text(&text);
+                        }
+                    } else if c == '\u{1a}' { //undo
+                        self.undo();
+                    } else if c == '\u{19}' { //redo
+                        self.redo();
+                    } else if (c == '\r' || c == '\n') && self.singleline {
+                        let value = self.get_value();
+                        self.exec_handler(ElementEvent::Submit, &value);
                     } else {
                         if c == '\r' {
                             c = '\n';
                         }
-                        if self.cursor.is_some() {
-                            if self.cursor_after {
-                                self.value.insert(self.cursor_index + 1,c);
-                            } else {
-                                self.value.insert(self.cursor_index,c);
-                            }
-                            if self.value.len() == 1 {
-                                self.cursor_index = 0;
-                                self.cursor_after = true;
-                            }
-                            else {
-                                self.cursor_index += 1;
-                            }
-                        }
+                        let (start, end) = self.selected();
+                        let kind = if start < end {
+                            EditKind::Replace
+                        } else {
+                            EditKind::Insert
+                        };
+                        self.input(kind, start, end, vec![c]);
                     }
                     handled = true;
                 }
@@ -392,23 +1182,66 @@ impl Element for TextBox {
                 && b == properties::Button::Left
                 {
                     if s == properties::ButtonState::Pressed{
-                        self.selecting = true;
+                        let now = Instant::now();
+                        self.presses = match self.last_press {
+                            Some(t) if now.duration_since(t) < MULTI_CLICK => self.presses % 3 + 1,
+                            _ => 1,
+                        };
+                        self.last_press = Some(now);
+                        self.drag_at = None;
                         self.set_cursor(&p);
+                        match self.presses {
+                            2 => {
+                                let (start, end) = self.word_at(self.cursor_index);
+                                self.set_selection(start, end);
+                            }
+                            3 => {
+                                let (start, end) = self.line_at(self.cursor_index);
+                                self.set_selection(start, end);
+                            }
+                            _ => {
+                                //shift+click extends from the anchor
+                                self.selecting = true;
+                                if !m.shift {
+                                    self.anchor = self.caret();
+                                }
+                            }
+                        }
                     }
                     else if s == properties::ButtonState::Released
                     {
+                        if self.selecting {
+                            self.set_cursor(&p);
+                        }
                         self.selecting = false;
-                        self.set_cursor(&p);
-                        handled = self.exec_handler(ElementEvent::Clicked, &m);
+                        self.publish_selection();
+                        if clicked {
+                            handled = self.exec_handler(ElementEvent::Clicked, &m);
+                        }
+                    }
+                } else if b == properties::Button::Left && s == properties::ButtonState::Released {
+                    self.selecting = false;
+                } else if !ext_ids.is_empty()
+                && ext_ids[0].0 == self.ext_id
+                && b == properties::Button::Middle
+                && s == properties::ButtonState::Pressed
+                && self.enabled
+                && self.editable
+                {
+                    //pastes the primary selection where it is clicked
+                    self.set_cursor(&p);
+                    self.anchor = self.caret();
+                    if let Some(text) = clipboard::get_primary_text() {
+                        self.insert_text(&text);
+                        handled = true;
                     }
                 }
             },
             PrimitiveEvent::CursorMoved(p) => {
-
-                if self.selecting && !ext_ids.is_empty() && ext_ids[0].0 == self.ext_id{
-
-                    println!("cursor moved");
+                //a drag goes on selecting when it leaves the box
+                if self.selecting {
                     self.set_cursor(&p);
+                    self.drag_at = Some(p);
 
                     /*let tmp = self.cache.get_char_at_pos(&p, &self.value);
                     if tmp.is_some() {
@@ -458,6 +1291,32 @@ impl Element for TextBox {
     }
 }
 
+//the x and the top of the caret before or after the char at `index`
+fn caret_spot(paras: &font::Paragraphs, index: usize, after: bool, size: f32) -> Option<(f32, f32)> {
+    let ch = paras.get_char_at_index(index)?;
+    Some((ch.get_caret_x(after), ch.get_position().y - size))
+}
+
+//what double clicking selects a run of
+#[derive(PartialEq)]
+enum CharKind {
+    Word,
+    Space,
+    Other,
+}
+
+impl CharKind {
+    fn of(c: char) -> CharKind {
+        if c.is_alphanumeric() || c == '_' {
+            CharKind::Word
+        } else if c.is_whitespace() && c != '\n' {
+            CharKind::Space
+        } else {
+            CharKind::Other
+        }
+    }
+}
+
 impl CanDisable for TextBox {
     fn set_enabled(&mut self, value: bool) {
         self.enabled = value;
@@ -0,0 +1,129 @@
+//which chars can be typed into a TextBox
+#[derive(Clone, Debug, PartialEq)]
+pub enum CharFilter {
+    Digits,
+    Letters,
+    Alphanumeric,
+    Number, //digits, a sign and a decimal point
+    OneOf(String),
+    NoneOf(String),
+}
+
+impl CharFilter {
+    pub fn allows(&self, c: char) -> bool {
+        match self {
+            CharFilter::Digits => c.is_ascii_digit(),
+            CharFilter::Letters => c.is_alphabetic(),
+            CharFilter::Alphanumeric => c.is_alphanumeric(),
+            CharFilter::Number => c.is_ascii_digit() || c == '.' || c == '-' || c == '+',
+            CharFilter::OneOf(chars) => chars.contains(c),
+            CharFilter::NoneOf(chars) => !chars.contains(c),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Slot {
+    Digit,
+    Letter,
+    Alphanumeric,
+    Literal(char),
+}
+
+impl Slot {
+    fn accepts(&self, c: char) -> bool {
+        match self {
+            Slot::Digit => c.is_ascii_digit(),
+            Slot::Letter => c.is_alphabetic(),
+            Slot::Alphanumeric => c.is_alphanumeric(),
+            Slot::Literal(l) => *l == c,
+        }
+    }
+}
+
+//The shape of a value such as a date or a phone number. In the pattern
+//'9' stands for a digit, 'a' for a letter and '*' for either, '\' makes
+//the char after it literal and every other char is literal. Literals
+//are put in as the chars around them are typed, so "99/99/9999" turns
+//"1231" into "12/31".
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mask {
+    slots: Vec<Slot>,
+}
+
+impl Mask {
+    pub fn new(pattern: &str) -> Mask {
+        let mut slots = vec![];
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            slots.push(match c {
+                '9' => Slot::Digit,
+                'a' => Slot::Letter,
+                '*' => Slot::Alphanumeric,
+                '\\' => match chars.next() {
+                    Some(l) => Slot::Literal(l),
+                    None => Slot::Literal('\\'),
+                },
+                _ => Slot::Literal(c),
+            });
+        }
+        Mask { slots }
+    }
+
+    //the length of a value that fills every slot
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    fn is_literal(&self, c: char) -> bool {
+        self.slots.contains(&Slot::Literal(c))
+    }
+
+    //Fits `text` into the mask and moves `caret` along with the chars
+    //around it. A literal out of place, such as one left behind by a
+    //deletion, is dropped. None if a char does not fit its slot or there
+    //are more chars than slots.
+    pub fn apply(&self, text: &[char], caret: usize) -> Option<(Vec<char>, usize)> {
+        let mut masked = vec![];
+        let mut at = 0;
+        for (i, c) in text.iter().enumerate() {
+            if i == caret {
+                at = masked.len();
+            }
+            //every slot filled so far has a char
+            loop {
+                match self.slots.get(masked.len()) {
+                    Some(Slot::Literal(l)) => {
+                        masked.push(*l);
+                        if *l == *c {
+                            break;
+                        }
+                    }
+                    Some(slot) if slot.accepts(*c) => {
+                        masked.push(*c);
+                        break;
+                    }
+                    Some(_) if self.is_literal(*c) => break,
+                    _ => return None,
+                }
+            }
+        }
+        if caret >= text.len() {
+            at = masked.len();
+        }
+        Some((masked, at))
+    }
+
+    //every slot is filled
+    pub fn is_complete(&self, text: &[char]) -> bool {
+        text.len() == self.slots.len()
+            && text
+                .iter()
+                .zip(self.slots.iter())
+                .all(|(c, s)| s.accepts(*c))
+    }
+}
@@ -1,11 +1,15 @@
 use std::any::Any;
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use webrender::api::*;
 
 use crate::elements::element::*;
+use crate::elements::layout::*;
 use crate::gui::font;
 use crate::gui::properties;
+use crate::gui::style;
+use crate::gui::window;
 use crate::util::*;
 
 pub struct VBox {
@@ -14,6 +18,9 @@ pub struct VBox {
     props: properties::Properties,
     bounds: properties::Extent,
     handlers: EventHandlers,
+    click: ClickState,
+    align_items: properties::CrossAlign,
+    relaid: bool, //the last frame was rendered to place a child again
 }
 
 impl VBox {
@@ -32,50 +39,80 @@ impl VBox {
                 dpi: 0.0,
             },
             handlers: EventHandlers::new(),
+            click: ClickState::default(),
+            align_items: properties::CrossAlign::Stretch,
+            relaid: false,
         }
     }
 
-    fn get_height_sums(&mut self) -> (f32, f32) {
-        let top = self.props.get_top();
-        let bottom = self.props.get_bottom();
+    //default placement of the children across the box, a child's
+    //AlignSelf overrides it
+    pub fn set_align_items(&mut self, align: properties::CrossAlign) {
+        self.align_items = align;
+    }
 
-        let mut stretchy: f32 = 0.0;
+    pub fn get_align_items(&self) -> properties::CrossAlign {
+        self.align_items.clone()
+    }
+
+    //pixel height taken by the margins and the children that do not
+    //stretch, and a stretch item for the top margin, every child and the
+    //bottom margin in that order
+    fn get_height_sums(
+        &mut self,
+        props: &properties::Properties,
+        computed: &Arc<properties::Properties>,
+        extent: &properties::Extent,
+    ) -> (f32, Vec<(f32, f32, f32)>) {
+        let parent = extent.h;
+        let top = props.get_top();
+        let bottom = props.get_bottom();
+        let none = properties::Unit::Pixel(0.0);
+        let unbounded = properties::Unit::Pixel(std::f32::INFINITY);
+
+        let mut stretchy = vec![stretch_item(&top, &none, &unbounded, parent)];
         let mut pixel: f32 = 0.0;
 
-        match top {
-            properties::Unit::Stretch(_s) => stretchy += _s,
-            properties::Unit::Pixel(_p) => pixel += _p,
-            _ => (),
+        if let properties::Unit::Pixel(_p) = top {
+            pixel += _p;
         }
 
-        match bottom {
-            properties::Unit::Stretch(_s) => stretchy += _s,
-            properties::Unit::Pixel(_p) => pixel += _p,
-            _ => (),
+        if let properties::Unit::Pixel(_p) = bottom {
+            pixel += _p;
         }
 
         for elm in self.children.iter() {
             if let Ok(ref _e) = elm.lock() {
                 let _p = _e.get_bounds().h;
+                let props = inherited(&**_e, computed).resolved(extent.w, extent.h);
+                let height = props.get_height();
 
-                match _e.get_properties().get_height() {
-                    properties::Unit::Stretch(_s) => stretchy += _s,
+                match height {
+                    properties::Unit::Stretch(_s) => (),
                     _ => {
                         if !_p.is_nan() && !_p.is_infinite() {
                             pixel += _p;
                         }
                     }
                 }
+                stretchy.push(stretch_item(
+                    &height,
+                    &props.get_min_height(),
+                    &props.get_max_height(),
+                    parent,
+                ));
             }
         }
 
+        stretchy.push(stretch_item(&bottom, &none, &unbounded, parent));
+
         (pixel, stretchy)
     }
 
-    fn get_width_sums(&mut self) -> (f32, f32) {
-        let left = self.props.get_left();
-        let right = self.props.get_right();
-        let width = self.props.get_width();
+    fn get_width_sums(&mut self, props: &properties::Properties) -> (f32, f32) {
+        let left = props.get_left();
+        let right = props.get_right();
+        let width = props.get_width();
 
         let mut stretchy: f32 = 0.0;
         let mut pixel: f32 = 0.0;
@@ -113,6 +150,10 @@ impl Element for VBox {
         self.ext_id
     }
 
+    fn get_kind(&self) -> &'static str {
+        "VBox"
+    }
+
     fn set(&mut self, prop: properties::Property) {
         self.props.set(prop);
     }
@@ -134,15 +175,18 @@ impl Element for VBox {
         _props: Option<Arc<properties::Properties>>,
         gen: &mut properties::IdGenerator,
     ) {
-        let bgcolor = self.props.get_bg_color();
-        let top = self.props.get_top();
-        let bottom = self.props.get_bottom();
-        let left = self.props.get_left();
-        let right = self.props.get_right();
-        let width = self.props.get_width();
-        let height = self.props.get_height();
-
-        let (wp_sum, ws_sum) = self.get_width_sums();
+        //the values children inherit from
+        let computed = Arc::new(style::computed("VBox", &self.props, &_props, vec![]));
+        let bgcolor = computed.get_bg_color();
+        let props = computed.resolved(extent.w, extent.h);
+        let top = props.get_top();
+        let bottom = props.get_bottom();
+        let left = props.get_left();
+        let right = props.get_right();
+        let width = props.get_width();
+        let height = props.get_height();
+
+        let (wp_sum, ws_sum) = self.get_width_sums(&props);
         let mut remaining_width = extent.w - wp_sum;
         if remaining_width < 0.0 {
             remaining_width = 0.0;
@@ -152,15 +196,18 @@ impl Element for VBox {
             w_stretchy_factor = 0.0;
         }
 
-        let (hp_sum, hs_sum) = self.get_height_sums();
+        let (hp_sum, h_items) = self.get_height_sums(&props, &computed, &extent);
         let mut remaining_height = extent.h - hp_sum;
         if remaining_height < 0.0 {
             remaining_height = 0.0;
         }
+        let hs_sum: f32 = h_items.iter().map(|i| i.0).sum();
         let mut h_stretchy_factor = remaining_height / hs_sum;
         if h_stretchy_factor.is_nan() {
             h_stretchy_factor = 0.0;
         }
+        //children that hit their min or max leave their share to the rest
+        let h_sizes = distribute(remaining_height, &h_items);
 
         let _id = gen.get();
         self.ext_id = _id;
@@ -175,7 +222,7 @@ impl Element for VBox {
         let mut next_y = 0.0;
 
         match top {
-            properties::Unit::Stretch(_s) => next_y = h_stretchy_factor * _s,
+            properties::Unit::Stretch(_s) => next_y = h_sizes[0],
             properties::Unit::Pixel(_p) => next_y = _p,
             _ => (),
         }
@@ -197,8 +244,15 @@ impl Element for VBox {
             properties::Unit::Pixel(_p) => remaining_width = _p,
             _ => (),
         }
+        remaining_width = clamp_size(
+            remaining_width,
+            &props.get_min_width(),
+            &props.get_max_width(),
+            extent.w,
+        );
 
-        for elm in self.children.iter_mut() {
+        let mut moved = false;
+        for (i, elm) in self.children.iter_mut().enumerate() {
             let mut child_extent = properties::Extent {
                 x: next_x + extent.x,
                 y: next_y + extent.y,
@@ -209,6 +263,24 @@ impl Element for VBox {
 
             match elm.lock() {
                 Ok(ref mut elm) => {
+                    let mut align = elm.get_properties().get_align_self();
+                    if align == properties::CrossAlign::Auto {
+                        align = self.align_items.clone();
+                    }
+                    //children that do not fill the box are placed by their
+                    //size and given only the space from there on
+                    let props = inherited(&**elm, &computed).resolved(extent.w, extent.h);
+                    let (cross, natural) = cross_size(
+                        &props.get_width(),
+                        &props.get_min_width(),
+                        &props.get_max_width(),
+                        remaining_width,
+                        elm.get_bounds().w,
+                    );
+                    let offset = cross_offset(&align, cross, remaining_width);
+                    child_extent.x += offset;
+                    child_extent.w = remaining_width - offset;
+
                     let e_height = elm.get_properties().get_height();
 
                     match e_height {
@@ -216,21 +288,41 @@ impl Element for VBox {
                             child_extent.h = _p;
                         }
                         properties::Unit::Stretch(_s) => {
-                            child_extent.h = h_stretchy_factor;
+                            //children scale their extent by their own factor,
+                            //one that does not stretch gets nothing
+                            child_extent.h = if _s > 0.0 { h_sizes[i + 1] / _s } else { 0.0 };
                         }
                         _ => (),
                     }
 
-                    elm.render(api, builder, child_extent, font_store, None, gen);
+                    validate_element(&**elm);
+                    elm.render(
+                        api,
+                        builder,
+                        child_extent,
+                        font_store,
+                        Some(computed.clone()),
+                        gen,
+                    );
                     let _ex = elm.get_bounds();
+                    if natural && offset > 0.0 && (sane(_ex.w) - cross).abs() > 0.5 {
+                        moved = true;
+                    }
                     next_y += _ex.h;
                 }
                 Err(_err_str) => panic!("unable to lock element : {}", _err_str),
             }
         }
+        //content that changed size is placed again straight away, but only
+        //once so content that changes with its place does not keep the
+        //window rendering
+        if moved && !self.relaid {
+            window::wake_at(Instant::now());
+        }
+        self.relaid = moved && !self.relaid;
 
         match bottom {
-            properties::Unit::Stretch(_s) => next_y += h_stretchy_factor * _s,
+            properties::Unit::Stretch(_s) => next_y += h_sizes[h_sizes.len() - 1],
             properties::Unit::Pixel(_p) => next_y += _p,
             _ => (),
         }
@@ -255,7 +347,12 @@ impl Element for VBox {
             x: extent.x,
             y: extent.y,
             w: next_x,
-            h: next_y,
+            h: clamp_size(
+                next_y,
+                &props.get_min_height(),
+                &props.get_max_height(),
+                extent.h,
+            ),
             dpi: extent.dpi,
         };
     }
@@ -264,6 +361,10 @@ impl Element for VBox {
         self.bounds.clone()
     }
 
+    fn get_min_size(&self) -> (f32, f32) {
+        min_size(&self.props, children_min_size(&self.children, false, true))
+    }
+
     fn on_primitive_event(&mut self, ext_ids: &[ItemTag], e: PrimitiveEvent) -> bool {
         let mut handled = false;
         for _child_elm in self.children.iter_mut() {
@@ -279,7 +380,8 @@ impl Element for VBox {
                         _child_elm.on_primitive_event(&[], PrimitiveEvent::SetFocus(false));
                     }
                 }
-                (PrimitiveEvent::Char(_c), Ok(ref mut _child_elm)) => {
+                (PrimitiveEvent::Char(_), Ok(ref mut _child_elm))
+                | (PrimitiveEvent::KeyInput(..), Ok(ref mut _child_elm)) => {
                     handled = _child_elm.on_primitive_event(&[], e.clone());
                     if handled {
                         break;
@@ -308,7 +410,8 @@ impl Element for VBox {
         }
         // if none of the children handled the event
         // see if you can handle it here
-        if !handled {
+        let over = !handled && !ext_ids.is_empty() && ext_ids[0].0 == self.ext_id;
+        if self.click.update(self.ext_id, over, &e) {
             if let PrimitiveEvent::Button(_p, _b, _s, m) = e {
                 handled = self.exec_handler(ElementEvent::Clicked, &m);
             }
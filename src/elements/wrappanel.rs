@@ -0,0 +1,445 @@
+use std::any::Any;
+use std::sync::{Arc, Mutex};
+
+use webrender::api::*;
+
+use crate::elements::element::*;
+use crate::elements::flex::Justify;
+use crate::elements::layout::*;
+use crate::gui::font;
+use crate::gui::properties;
+use crate::gui::properties::{CrossAlign, Unit};
+use crate::gui::style;
+use crate::util::*;
+
+//A container that places its children in rows, left to right (right to
+//left when rtl is set) and starts a new row when the next child does
+//not fit the width. Children are not resized, Natural children are
+//given the whole width to measure themselves against.
+pub struct WrapPanel {
+    ext_id: u64,
+    children: Vec<Arc<Mutex<dyn Element>>>,
+    props: properties::Properties,
+    bounds: properties::Extent,
+    handlers: EventHandlers,
+    click: ClickState,
+    item_spacing: f32,
+    row_spacing: f32,
+    row_align: Justify,
+    align_items: CrossAlign,
+    rtl: bool,
+}
+
+//size of a child as measured for placement
+struct WrapItem {
+    w: f32,
+    h: f32,
+    stretch_h: bool,
+    align: CrossAlign,
+}
+
+impl WrapPanel {
+    pub fn new() -> Self {
+        let mut props = properties::Properties::new();
+        props.default();
+        WrapPanel {
+            ext_id: 0,
+            children: Vec::new(),
+            props,
+            bounds: properties::Extent::new(),
+            handlers: EventHandlers::new(),
+            click: ClickState::default(),
+            item_spacing: 0.0,
+            row_spacing: 0.0,
+            row_align: Justify::Start,
+            align_items: CrossAlign::Start,
+            rtl: false,
+        }
+    }
+
+    //space between children on a row
+    pub fn set_item_spacing(&mut self, spacing: f32) {
+        self.item_spacing = spacing;
+    }
+
+    pub fn get_item_spacing(&self) -> f32 {
+        self.item_spacing
+    }
+
+    //space between rows
+    pub fn set_row_spacing(&mut self, spacing: f32) {
+        self.row_spacing = spacing;
+    }
+
+    pub fn get_row_spacing(&self) -> f32 {
+        self.row_spacing
+    }
+
+    //distribution of the space left over on every row
+    pub fn set_row_align(&mut self, align: Justify) {
+        self.row_align = align;
+    }
+
+    pub fn get_row_align(&self) -> Justify {
+        self.row_align.clone()
+    }
+
+    //placement of children within the height of their row
+    pub fn set_align_items(&mut self, align: CrossAlign) {
+        self.align_items = align;
+    }
+
+    pub fn get_align_items(&self) -> CrossAlign {
+        self.align_items.clone()
+    }
+
+    //rows fill from the right, the first child is the rightmost
+    pub fn set_rtl(&mut self, rtl: bool) {
+        self.rtl = rtl;
+    }
+
+    pub fn get_rtl(&self) -> bool {
+        self.rtl
+    }
+
+    //Renders the Natural children where nothing is shown, with the space
+    //they are given for real, so their bounds are their size this frame
+    //rather than the size they had the last time.
+    fn size_natural(
+        &self,
+        api: &RenderApi,
+        pipeline_id: PipelineId,
+        extent: properties::Extent,
+        font_store: &mut font::FontStore,
+        computed: &Arc<properties::Properties>,
+        gen: &mut properties::IdGenerator,
+    ) {
+        let mut scratch = DisplayListBuilder::new(pipeline_id, LayoutSize::new(extent.w, extent.h));
+        for elm in self.children.iter() {
+            match elm.lock() {
+                Ok(ref mut elm) => {
+                    let raw = elm.get_properties();
+                    if raw.get_width() == Unit::Natural || raw.get_height() == Unit::Natural {
+                        elm.render(
+                            api,
+                            &mut scratch,
+                            extent.clone(),
+                            font_store,
+                            Some(computed.clone()),
+                            gen,
+                        );
+                    }
+                }
+                Err(_err_str) => panic!("unable to lock element : {}", _err_str),
+            }
+        }
+    }
+
+    fn measure(&self, w: f32, h: f32, computed: &Arc<properties::Properties>) -> Vec<WrapItem> {
+        let mut items = vec![];
+        for elm in self.children.iter() {
+            match elm.lock() {
+                Ok(ref _e) => {
+                    let props = inherited(&**_e, computed).resolved(w, h);
+                    let b = _e.get_bounds();
+                    let item_w = match props.get_width() {
+                        Unit::Pixel(_p) => _p,
+                        Unit::Natural => sane(b.w),
+                        _ => w,
+                    };
+                    let (item_h, stretch_h) = match props.get_height() {
+                        Unit::Pixel(_p) => (_p, false),
+                        Unit::Stretch(_) | Unit::Extent => (0.0, true),
+                        _ => (sane(b.h), false),
+                    };
+                    let mut align = props.get_align_self();
+                    if align == CrossAlign::Auto {
+                        align = self.align_items.clone();
+                    }
+                    items.push(WrapItem {
+                        w: clamp_size(item_w, &props.get_min_width(), &props.get_max_width(), w),
+                        h: clamp_size(item_h, &props.get_min_height(), &props.get_max_height(), h),
+                        stretch_h,
+                        align,
+                    });
+                }
+                Err(_err_str) => panic!("unable to lock element : {}", _err_str),
+            }
+        }
+        items
+    }
+
+    //indices of the children on every row
+    fn break_rows(&self, items: &[WrapItem], width: f32) -> Vec<Vec<usize>> {
+        let mut rows = vec![];
+        let mut row = vec![];
+        let mut used = 0.0;
+        for (i, item) in items.iter().enumerate() {
+            let next = if row.is_empty() {
+                item.w
+            } else {
+                used + self.item_spacing + item.w
+            };
+            if !row.is_empty() && next > width {
+                rows.push(row);
+                row = vec![];
+                used = item.w;
+            } else {
+                used = next;
+            }
+            row.push(i);
+        }
+        if !row.is_empty() {
+            rows.push(row);
+        }
+        rows
+    }
+}
+
+impl Default for WrapPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Element for WrapPanel {
+    fn get_ext_id(&self) -> u64 {
+        self.ext_id
+    }
+
+    fn get_kind(&self) -> &'static str {
+        "WrapPanel"
+    }
+
+    fn set(&mut self, prop: properties::Property) {
+        self.props.set(prop);
+    }
+
+    fn get_properties(&self) -> properties::Properties {
+        self.props.clone()
+    }
+
+    fn render(
+        &mut self,
+        api: &RenderApi,
+        builder: &mut DisplayListBuilder,
+        extent: properties::Extent,
+        font_store: &mut font::FontStore,
+        _props: Option<Arc<properties::Properties>>,
+        gen: &mut properties::IdGenerator,
+    ) {
+        //the values children inherit from
+        let computed = Arc::new(style::computed("WrapPanel", &self.props, &_props, vec![]));
+        let bgcolor = computed.get_bg_color();
+        let props = computed.resolved(extent.w, extent.h);
+
+        let width = container_size(
+            props.get_width(),
+            extent.w,
+            &props.get_min_width(),
+            &props.get_max_width(),
+        );
+        let height = container_size(
+            props.get_height(),
+            extent.h,
+            &props.get_min_height(),
+            &props.get_max_height(),
+        );
+        //a Natural width still wraps at the space it was given
+        let avail_w = width.unwrap_or(extent.w);
+
+        let measured = properties::Extent {
+            w: avail_w,
+            ..extent.clone()
+        };
+        self.size_natural(
+            api,
+            builder.pipeline_id,
+            measured,
+            font_store,
+            &computed,
+            gen,
+        );
+        let items = self.measure(avail_w, extent.h, &computed);
+        let rows = self.break_rows(&items, avail_w);
+
+        let mut row_heights = vec![];
+        let mut content_w: f32 = 0.0;
+        for row in rows.iter() {
+            let used: f32 = row.iter().map(|i| items[*i].w).sum::<f32>()
+                + self.item_spacing * (row.len() as f32 - 1.0);
+            content_w = content_w.max(used);
+            row_heights.push(row.iter().map(|i| items[*i].h).fold(0.0, f32::max));
+        }
+        let content_h = row_heights.iter().sum::<f32>()
+            + self.row_spacing * (row_heights.len() as f32 - 1.0).max(0.0);
+
+        let w = width.unwrap_or(content_w);
+        let h = height.unwrap_or(content_h);
+
+        self.bounds = properties::Extent {
+            x: extent.x,
+            y: extent.y,
+            w,
+            h,
+            dpi: extent.dpi,
+        };
+
+        let _id = gen.get();
+        self.ext_id = _id;
+
+        let mut info = LayoutPrimitiveInfo::new((extent.x, extent.y).by(w, h));
+        info.tag = Some((_id, 0));
+        builder.push_rect(&info, bgcolor);
+
+        let mut y = 0.0;
+        for (r, row) in rows.iter().enumerate() {
+            let row_h = row_heights[r];
+            let used: f32 = row.iter().map(|i| items[*i].w).sum::<f32>()
+                + self.item_spacing * (row.len() as f32 - 1.0);
+            let (offset, extra) = justify_offsets(&self.row_align, w - used, row.len());
+
+            let mut x = offset;
+            for i in row.iter() {
+                let item = &items[*i];
+                let (item_h, y_off) = if item.stretch_h || item.align == CrossAlign::Stretch {
+                    (row_h.max(item.h), 0.0)
+                } else {
+                    (item.h, cross_offset(&item.align, item.h, row_h))
+                };
+                let item_x = if self.rtl { w - x - item.w } else { x };
+
+                match self.children[*i].lock() {
+                    Ok(ref mut elm) => {
+                        //Natural children measure themselves against the whole
+                        //panel rather than the size they had last time
+                        let raw = elm.get_properties();
+                        let child_w = match raw.get_width() {
+                            Unit::Natural => avail_w,
+                            ref unit => given_size(unit, item.w, w),
+                        };
+                        let child_h = match raw.get_height() {
+                            Unit::Natural => extent.h,
+                            ref unit => given_size(unit, item_h, row_h),
+                        };
+                        let child_extent = properties::Extent {
+                            x: extent.x + item_x,
+                            y: extent.y + y + y_off,
+                            w: child_w,
+                            h: child_h,
+                            dpi: extent.dpi,
+                        };
+                        validate_element(&**elm);
+                        elm.render(
+                            api,
+                            builder,
+                            child_extent,
+                            font_store,
+                            Some(computed.clone()),
+                            gen,
+                        );
+                    }
+                    Err(_err_str) => panic!("unable to lock element : {}", _err_str),
+                }
+
+                x += item.w + self.item_spacing + extra;
+            }
+
+            y += row_h + self.row_spacing;
+        }
+    }
+
+    fn get_bounds(&self) -> properties::Extent {
+        self.bounds.clone()
+    }
+
+    //rows break wherever they have to, so only the widest child counts
+    fn get_min_size(&self) -> (f32, f32) {
+        min_size(&self.props, children_min_size(&self.children, false, false))
+    }
+
+    fn on_primitive_event(&mut self, ext_ids: &[ItemTag], e: PrimitiveEvent) -> bool {
+        let mut handled = false;
+        for _child_elm in self.children.iter_mut() {
+            match (&e, _child_elm.lock()) {
+                (PrimitiveEvent::SetFocus(_), Ok(ref mut _child_elm)) => {
+                    if ext_ids.len() > 1
+                        && ext_ids[0].0 == self.ext_id
+                        && ext_ids[1].0 == _child_elm.get_ext_id()
+                    {
+                        _child_elm
+                            .on_primitive_event(&ext_ids[1..], PrimitiveEvent::SetFocus(true));
+                    } else {
+                        _child_elm.on_primitive_event(&[], PrimitiveEvent::SetFocus(false));
+                    }
+                }
+                (PrimitiveEvent::Char(_), Ok(ref mut _child_elm))
+                | (PrimitiveEvent::KeyInput(..), Ok(ref mut _child_elm)) => {
+                    handled = _child_elm.on_primitive_event(&[], e.clone());
+                    if handled {
+                        break;
+                    }
+                }
+                (PrimitiveEvent::HoverBegin(_), Ok(ref mut _child_elm)) => {
+                    _child_elm.on_primitive_event(&[], e.clone());
+                }
+                (PrimitiveEvent::HoverEnd(_), Ok(ref mut _child_elm)) => {
+                    _child_elm.on_primitive_event(&[], e.clone());
+                }
+                (_, Ok(ref mut _child_elm)) => {
+                    if !handled {
+                        if ext_ids.len() == 1 {
+                            handled = _child_elm.on_primitive_event(&[], e.clone());
+                        } else if ext_ids.len() > 1 {
+                            handled = _child_elm.on_primitive_event(&ext_ids[1..], e.clone());
+                        }
+                    }
+                }
+                (_, Err(_err_str)) => {
+                    //this should be unreachable
+                    panic!("unable to lock element : {}", _err_str)
+                }
+            }
+        }
+        // if none of the children handled the event
+        // see if you can handle it here
+        let over = !handled && !ext_ids.is_empty() && ext_ids[0].0 == self.ext_id;
+        if self.click.update(self.ext_id, over, &e) {
+            if let PrimitiveEvent::Button(_p, _b, _s, m) = e {
+                handled = self.exec_handler(ElementEvent::Clicked, &m);
+            }
+        }
+        handled
+    }
+
+    fn set_handler(&mut self, _e: ElementEvent, _f: EventFn) {
+        self.handlers.insert(_e, _f);
+    }
+
+    fn exec_handler(&mut self, _e: ElementEvent, _d: &dyn Any) -> bool {
+        let h = self.handlers.get_mut(&_e).cloned();
+        if let Some(mut h) = h {
+            h.call(self, _d)
+        } else {
+            false
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl HasChildren for WrapPanel {
+    fn get_child(&self, i: u32) -> Option<Arc<Mutex<dyn Element>>> {
+        self.children.get(i as usize).cloned()
+    }
+
+    fn append(&mut self, e: Arc<Mutex<dyn Element>>) -> Option<Arc<Mutex<dyn Element>>> {
+        self.children.push(e);
+        None
+    }
+}
@@ -0,0 +1,185 @@
+use std::cell::RefCell;
+use std::error::Error;
+use std::sync::Mutex;
+
+#[cfg(target_os = "linux")]
+use ::clipboard::x11_clipboard::{Primary, X11ClipboardContext};
+use ::clipboard::{ClipboardContext, ClipboardProvider};
+
+//Where copied text is kept. Elements go through `get_text`/`set_text`
+//and the backend can be swapped, an in memory one for tests where there
+//is no display.
+pub trait Clipboard: Send {
+    fn get(&mut self) -> Option<String>;
+    fn set(&mut self, text: String);
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MemoryClipboard {
+    text: Option<String>,
+}
+
+impl MemoryClipboard {
+    pub fn new() -> MemoryClipboard {
+        MemoryClipboard { text: None }
+    }
+}
+
+impl Clipboard for MemoryClipboard {
+    fn get(&mut self) -> Option<String> {
+        self.text.clone()
+    }
+
+    fn set(&mut self, text: String) {
+        self.text = Some(text);
+    }
+}
+
+//The clipboard of the platform, or the X11 primary selection which
+//holds whatever was selected last. When the platform can not be reached
+//the text is kept in memory instead.
+#[derive(Clone, Debug, Default)]
+pub struct SystemClipboard {
+    primary: bool,
+    fallback: MemoryClipboard,
+}
+
+impl SystemClipboard {
+    pub fn new() -> SystemClipboard {
+        SystemClipboard {
+            primary: false,
+            fallback: MemoryClipboard::new(),
+        }
+    }
+
+    //only X11 has a primary selection, elsewhere it stays in memory
+    pub fn primary() -> SystemClipboard {
+        SystemClipboard {
+            primary: true,
+            fallback: MemoryClipboard::new(),
+        }
+    }
+}
+
+impl Clipboard for SystemClipboard {
+    fn get(&mut self) -> Option<String> {
+        let contents = if self.primary {
+            read_primary()
+        } else {
+            with_context(|c| c.get_contents())
+        };
+        match contents {
+            Ok(s) => Some(s),
+            Err(_) => self.fallback.get(),
+        }
+    }
+
+    fn set(&mut self, text: String) {
+        let done = if self.primary {
+            write_primary(text.clone())
+        } else {
+            with_context(|c| c.set_contents(text.clone()))
+        };
+        if done.is_err() {
+            self.fallback.set(text);
+        }
+    }
+}
+
+thread_local! {
+    //Connections to the platform clipboard, made on first use and kept
+    //as each one opens a display connection. They are per thread as some
+    //platforms do not let them move between threads.
+    static CONTEXT: RefCell<Option<ClipboardContext>> = RefCell::new(None);
+}
+
+fn with_context<T, F>(f: F) -> Result<T, Box<dyn Error>>
+where
+    F: FnOnce(&mut ClipboardContext) -> Result<T, Box<dyn Error>>,
+{
+    CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        if context.is_none() {
+            *context = Some(ClipboardContext::new()?);
+        }
+        match *context {
+            Some(ref mut c) => f(c),
+            None => Err("no clipboard".into()),
+        }
+    })
+}
+
+#[cfg(target_os = "linux")]
+thread_local! {
+    static PRIMARY_CONTEXT: RefCell<Option<X11ClipboardContext<Primary>>> = RefCell::new(None);
+}
+
+#[cfg(target_os = "linux")]
+fn with_primary<T, F>(f: F) -> Result<T, Box<dyn Error>>
+where
+    F: FnOnce(&mut X11ClipboardContext<Primary>) -> Result<T, Box<dyn Error>>,
+{
+    PRIMARY_CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        if context.is_none() {
+            *context = Some(X11ClipboardContext::new()?);
+        }
+        match *context {
+            Some(ref mut c) => f(c),
+            None => Err("no primary selection".into()),
+        }
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn read_primary() -> Result<String, Box<dyn Error>> {
+    with_primary(|c| c.get_contents())
+}
+
+#[cfg(target_os = "linux")]
+fn write_primary(text: String) -> Result<(), Box<dyn Error>> {
+    with_primary(|c| c.set_contents(text))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_primary() -> Result<String, Box<dyn Error>> {
+    Err("no primary selection".into())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn write_primary(_text: String) -> Result<(), Box<dyn Error>> {
+    Err("no primary selection".into())
+}
+
+lazy_static! {
+    static ref CLIPBOARD: Mutex<Box<dyn Clipboard>> = Mutex::new(Box::new(SystemClipboard::new()));
+    static ref PRIMARY: Mutex<Box<dyn Clipboard>> =
+        Mutex::new(Box::new(SystemClipboard::primary()));
+}
+
+//replaces where copied text goes
+pub fn set_backend(backend: Box<dyn Clipboard>) {
+    *CLIPBOARD.lock().unwrap() = backend;
+}
+
+//replaces where selected text goes
+pub fn set_primary_backend(backend: Box<dyn Clipboard>) {
+    *PRIMARY.lock().unwrap() = backend;
+}
+
+pub fn get_text() -> Option<String> {
+    CLIPBOARD.lock().unwrap().get()
+}
+
+pub fn set_text(text: String) {
+    CLIPBOARD.lock().unwrap().set(text);
+}
+
+//the text selected last, pasted with a middle click
+pub fn get_primary_text() -> Option<String> {
+    PRIMARY.lock().unwrap().get()
+}
+
+pub fn set_primary_text(text: String) {
+    PRIMARY.lock().unwrap().set(text);
+}
@@ -0,0 +1,454 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use webrender::api::ColorF;
+
+use crate::gui::properties::{Align, CrossAlign, Property, Unit, VerticalAlign};
+use crate::gui::style::{Selector, Stylesheet};
+
+//where in the text a stylesheet could not be read, both start at 1
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+//Reads a stylesheet written as a subset of css.
+//
+//    HBox Button, #display {
+//        font-size: 32px;
+//        color: #333;
+//        width: calc(100% - 40px);
+//    }
+//    Button:hover { background-color: rgb(128, 153, 153); }
+pub fn parse(text: &str) -> Result<Stylesheet, ParseError> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+        line: 1,
+        column: 1,
+    };
+    parser.sheet()
+}
+
+//parses the file at `path`, errors reading the file are reported at 1:1
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Stylesheet, ParseError> {
+    match fs::read_to_string(path) {
+        Ok(text) => parse(&text),
+        Err(e) => Err(ParseError {
+            line: 1,
+            column: 1,
+            message: e.to_string(),
+        }),
+    }
+}
+
+//For development. Makes the stylesheet at `path` the active one and
+//loads it again every time the file changes, so open windows restyle
+//without a restart. A stylesheet with errors is reported and the one
+//before it stays active, and so is a file that can not be found. The
+//file is watched until the returned Watcher is dropped.
+pub fn watch<P: AsRef<Path>>(path: P) -> Watcher {
+    let path: PathBuf = path.as_ref().to_path_buf();
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = stop.clone();
+    let thread = thread::spawn(move || {
+        let mut last: Option<SystemTime> = None;
+        let mut missing = false;
+        while !stopped.load(Ordering::SeqCst) {
+            let modified = match fs::metadata(&path).and_then(|m| m.modified()) {
+                Ok(modified) => {
+                    missing = false;
+                    Some(modified)
+                }
+                Err(e) => {
+                    if !missing {
+                        eprintln!("{}: {}", path.display(), e);
+                        missing = true;
+                    }
+                    None
+                }
+            };
+            if modified.is_some() && modified != last {
+                last = modified;
+                match load(&path) {
+                    Ok(sheet) => Stylesheet::set(sheet),
+                    Err(e) => eprintln!("{}:{}", path.display(), e),
+                }
+            }
+            thread::sleep(Duration::from_millis(500));
+        }
+    });
+    Watcher {
+        stop,
+        thread: Some(thread),
+    }
+}
+
+//stops watching a stylesheet when dropped
+pub struct Watcher {
+    stop: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, line: usize, column: usize, message: String) -> ParseError {
+        ParseError {
+            line,
+            column,
+            message,
+        }
+    }
+
+    //whitespace and comments
+    fn skip(&mut self) -> Result<(), ParseError> {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                Some('/') if self.chars.get(self.pos + 1) == Some(&'*') => {
+                    let (line, column) = (self.line, self.column);
+                    self.bump();
+                    self.bump();
+                    loop {
+                        match self.bump() {
+                            Some('*') if self.peek() == Some('/') => {
+                                self.bump();
+                                break;
+                            }
+                            Some(_) => (),
+                            None => {
+                                return Err(self.error(
+                                    line,
+                                    column,
+                                    String::from("comment is not closed"),
+                                ))
+                            }
+                        }
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    //text up to one of `stops`, which is not consumed
+    fn until(&mut self, stops: &[char]) -> String {
+        let mut s = String::new();
+        while let Some(c) = self.peek() {
+            if stops.contains(&c) {
+                break;
+            }
+            s.push(c);
+            self.bump();
+        }
+        s
+    }
+
+    fn sheet(&mut self) -> Result<Stylesheet, ParseError> {
+        let mut sheet = Stylesheet::new();
+        loop {
+            self.skip()?;
+            if self.peek().is_none() {
+                return Ok(sheet);
+            }
+
+            let (line, column) = (self.line, self.column);
+            let selectors = self.until(&['{', '}', ';']);
+            if self.bump() != Some('{') {
+                return Err(self.error(line, column, String::from("expected '{' after selector")));
+            }
+            let mut parsed = vec![];
+            for s in selectors.split(',') {
+                match Selector::parse(s) {
+                    Ok(_s) => parsed.push(_s),
+                    Err(e) => return Err(self.error(line, column, e)),
+                }
+            }
+
+            let properties = self.declarations()?;
+            for selector in parsed {
+                sheet.push_rule(selector, properties.clone());
+            }
+        }
+    }
+
+    //`name: value;` pairs up to and including the closing brace
+    fn declarations(&mut self) -> Result<Vec<Property>, ParseError> {
+        let mut properties = vec![];
+        loop {
+            self.skip()?;
+            let (line, column) = (self.line, self.column);
+            match self.peek() {
+                Some('}') => {
+                    self.bump();
+                    return Ok(properties);
+                }
+                None => return Err(self.error(line, column, String::from("expected '}'"))),
+                _ => (),
+            }
+
+            let name = self.until(&[':', ';', '{', '}']).trim().to_lowercase();
+            if self.bump() != Some(':') {
+                return Err(self.error(line, column, format!("expected ':' after {}", name)));
+            }
+            self.skip()?;
+            let (value_line, value_column) = (self.line, self.column);
+            let value = self.until(&[';', '}']);
+            if self.peek() == Some(';') {
+                self.bump();
+            }
+
+            match declaration(&name, value.trim()) {
+                Ok(Some(_p)) => properties.push(_p),
+                Ok(None) => {
+                    return Err(self.error(line, column, format!("unknown property {}", name)))
+                }
+                Err(e) => return Err(self.error(value_line, value_column, e)),
+            }
+        }
+    }
+}
+
+//None if there is no property called `name`
+fn declaration(name: &str, value: &str) -> Result<Option<Property>, String> {
+    let property = match name {
+        "font-family" => Property::Family(value.trim_matches(|c| c == '"' || c == '\'').to_owned()),
+        "font-size" => Property::Size(font_size(value)?),
+        "color" => Property::Color(color(value)?),
+        "background-color" => Property::BgColor(color(value)?),
+        "hover-color" => Property::HoverColor(color(value)?),
+        "hover-background-color" => Property::HoverBgColor(color(value)?),
+        "focus-color" => Property::FocusColor(color(value)?),
+        "focus-background-color" => Property::FocusBgColor(color(value)?),
+        "active-color" => Property::ActiveColor(color(value)?),
+        "active-background-color" => Property::ActiveBgColor(color(value)?),
+        "disabled-color" => Property::DisabledColor(color(value)?),
+        "disabled-background-color" => Property::DisabledBgColor(color(value)?),
+        "invalid-color" => Property::InvalidColor(color(value)?),
+        "invalid-background-color" => Property::InvalidBgColor(color(value)?),
+        "selection-background-color" => Property::SelectionBgColor(color(value)?),
+        "caret-color" => Property::CaretColor(color(value)?),
+        "caret-width" => Property::CaretWidth(number(value.trim_end_matches("px"))?),
+        "gutter-color" => Property::GutterColor(color(value)?),
+        "gutter-background-color" => Property::GutterBgColor(color(value)?),
+        "line-highlight-color" => Property::LineHighlightColor(color(value)?),
+        "bracket-match-color" => Property::BracketMatchColor(color(value)?),
+        "text-align" => Property::TextAlign(match value {
+            "left" => Align::Left,
+            "center" | "middle" => Align::Middle,
+            "right" => Align::Right,
+            _ => return Err(format!("unknown text-align {}", value)),
+        }),
+        "vertical-align" => Property::VerticalAlign(match value {
+            "top" => VerticalAlign::Top,
+            "center" | "middle" => VerticalAlign::Center,
+            "bottom" => VerticalAlign::Bottom,
+            "baseline" => VerticalAlign::Baseline,
+            _ => return Err(format!("unknown vertical-align {}", value)),
+        }),
+        "align-self" => Property::AlignSelf(match value {
+            "auto" => CrossAlign::Auto,
+            "start" => CrossAlign::Start,
+            "center" => CrossAlign::Center,
+            "end" => CrossAlign::End,
+            "stretch" => CrossAlign::Stretch,
+            _ => return Err(format!("unknown align-self {}", value)),
+        }),
+        "left" => Property::Left(unit(value)?),
+        "width" => Property::Width(unit(value)?),
+        "right" => Property::Right(unit(value)?),
+        "top" => Property::Top(unit(value)?),
+        "height" => Property::Height(unit(value)?),
+        "bottom" => Property::Bottom(unit(value)?),
+        "min-width" => Property::MinWidth(unit(value)?),
+        "min-height" => Property::MinHeight(unit(value)?),
+        "max-width" => Property::MaxWidth(unit(value)?),
+        "max-height" => Property::MaxHeight(unit(value)?),
+        _ => return Ok(None),
+    };
+    Ok(Some(property))
+}
+
+fn number(s: &str) -> Result<f32, String> {
+    s.trim()
+        .parse::<f32>()
+        .map_err(|_| format!("expected a number, found {}", s))
+}
+
+fn font_size(s: &str) -> Result<i32, String> {
+    let n = number(s.trim_end_matches("px"))?;
+    Ok(n.round() as i32)
+}
+
+//natural, extent, 10px, 1fr (a stretch), 50%, 2em, 1rem, 10vw, 10vh
+//and calc() of those added or subtracted
+fn unit(s: &str) -> Result<Unit, String> {
+    let s = s.trim();
+    if s.starts_with("calc(") && s.ends_with(')') {
+        return calc(&s[5..s.len() - 1]);
+    }
+    let suffixes: [(&str, fn(f32) -> Unit); 7] = [
+        ("px", Unit::Pixel),
+        ("fr", Unit::Stretch),
+        ("%", Unit::Percent),
+        ("rem", Unit::Rem),
+        ("em", Unit::Em),
+        ("vw", Unit::Vw),
+        ("vh", Unit::Vh),
+    ];
+    match s {
+        "natural" | "auto" => return Ok(Unit::Natural),
+        "extent" => return Ok(Unit::Extent),
+        "infinity" | "none" => return Ok(Unit::Pixel(std::f32::INFINITY)),
+        _ => (),
+    }
+    for (suffix, make) in suffixes.iter() {
+        if s.ends_with(suffix) {
+            return Ok(make(number(&s[..s.len() - suffix.len()])?));
+        }
+    }
+    if number(s)? == 0.0 {
+        return Ok(Unit::Pixel(0.0));
+    }
+    Err(format!("length {} needs a unit", s))
+}
+
+//terms separated by + or -, which need whitespace around them as in css
+fn calc(s: &str) -> Result<Unit, String> {
+    let mut terms = vec![];
+    let mut sign = 1.0;
+    for (i, part) in s.split_whitespace().enumerate() {
+        if i % 2 == 1 {
+            sign = match part {
+                "+" => 1.0,
+                "-" => -1.0,
+                _ => return Err(format!("expected + or - in calc, found {}", part)),
+            };
+            continue;
+        }
+        let term = match unit(part)? {
+            Unit::Pixel(_p) => Unit::Pixel(sign * _p),
+            Unit::Percent(_p) => Unit::Percent(sign * _p),
+            Unit::Em(_e) => Unit::Em(sign * _e),
+            Unit::Rem(_r) => Unit::Rem(sign * _r),
+            Unit::Vw(_v) => Unit::Vw(sign * _v),
+            Unit::Vh(_v) => Unit::Vh(sign * _v),
+            _ => return Err(format!("{} can not be used in calc", part)),
+        };
+        terms.push(term);
+    }
+    if terms.is_empty() {
+        return Err(String::from("empty calc"));
+    }
+    Ok(Unit::Calc(terms))
+}
+
+//#rgb, #rrggbb, #rrggbbaa, rgb(r, g, b), rgba(r, g, b, a) and a few names
+fn color(s: &str) -> Result<ColorF, String> {
+    let s = s.trim();
+    match s {
+        "transparent" => return Ok(ColorF::new(0.0, 0.0, 0.0, 0.0)),
+        "black" => return Ok(ColorF::new(0.0, 0.0, 0.0, 1.0)),
+        "white" => return Ok(ColorF::new(1.0, 1.0, 1.0, 1.0)),
+        "gray" | "grey" => return Ok(ColorF::new(0.5, 0.5, 0.5, 1.0)),
+        "red" => return Ok(ColorF::new(1.0, 0.0, 0.0, 1.0)),
+        "green" => return Ok(ColorF::new(0.0, 0.5, 0.0, 1.0)),
+        "blue" => return Ok(ColorF::new(0.0, 0.0, 1.0, 1.0)),
+        _ => (),
+    }
+
+    if s.starts_with('#') {
+        let hex: Vec<u32> = s[1..]
+            .chars()
+            .map(|c| c.to_digit(16))
+            .collect::<Option<Vec<u32>>>()
+            .ok_or_else(|| format!("invalid color {}", s))?;
+        let channels: Vec<f32> = match hex.len() {
+            3 => hex.iter().map(|h| (h * 17) as f32 / 255.0).collect(),
+            6 | 8 => hex
+                .chunks(2)
+                .map(|h| (h[0] * 16 + h[1]) as f32 / 255.0)
+                .collect(),
+            _ => return Err(format!("invalid color {}", s)),
+        };
+        let a = if channels.len() == 4 {
+            channels[3]
+        } else {
+            1.0
+        };
+        return Ok(ColorF::new(channels[0], channels[1], channels[2], a));
+    }
+
+    let args = if s.starts_with("rgba(") && s.ends_with(')') {
+        &s[5..s.len() - 1]
+    } else if s.starts_with("rgb(") && s.ends_with(')') {
+        &s[4..s.len() - 1]
+    } else {
+        return Err(format!("invalid color {}", s));
+    };
+    let values = args
+        .split(',')
+        .map(number)
+        .collect::<Result<Vec<f32>, String>>()?;
+    match values.len() {
+        3 => Ok(ColorF::new(
+            values[0] / 255.0,
+            values[1] / 255.0,
+            values[2] / 255.0,
+            1.0,
+        )),
+        4 => Ok(ColorF::new(
+            values[0] / 255.0,
+            values[1] / 255.0,
+            values[2] / 255.0,
+            values[3],
+        )),
+        _ => Err(format!("invalid color {}", s)),
+    }
+}
@@ -264,6 +264,15 @@ impl Char {
         }
         ret
     }
+    //x of the edge the caret is at before or after the char, which is
+    //the right edge first in right to left text
+    pub fn get_caret_x(&self, after: bool) -> f32 {
+        if after != self.rtl {
+            self.position.x + self.metric.advance.x
+        } else {
+            self.position.x
+        }
+    }
     pub fn get_rtl(&self) -> bool {
         self.rtl
     }
@@ -272,6 +281,16 @@ impl Char {
     }
 }
 
+//A place on a line the caret can be at, the left or right edge of the
+//char at `index`. `after` is whether the edge follows the char in the
+//text rather than comes before it.
+#[derive(Debug, Clone)]
+pub struct CaretStop {
+    pub x: f32,
+    pub index: usize,
+    pub after: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct Segment {
     rtl: bool,
@@ -317,14 +336,17 @@ impl Segment {
 
         let mut _x = 0.;
 
+        //right to left glyphs come in visual order, the last char first
+        let n = self.chars.len();
         let mut i = 0;
-        while i < self.chars.len() {
+        while i < n {
             let (glyph, ref metric) = glyphs[i];
+            let c = if self.rtl { n - 1 - i } else { i };
 
-            self.chars[i].glyph = glyph;
-            self.chars[i].metric = metric.clone();
-            self.chars[i].position.x = _x;
-            self.chars[i].position.y = size;
+            self.chars[c].glyph = glyph;
+            self.chars[c].metric = metric.clone();
+            self.chars[c].position.x = _x;
+            self.chars[c].position.y = size;
 
             i += 1;
             _x += metric.advance.x;
@@ -340,7 +362,13 @@ impl Segment {
         self.extent.y = y;
 
         let mut _x = x;
-        for ch in self.chars.iter_mut() {
+        let n = self.chars.len();
+        for i in 0..n {
+            let ch = if self.rtl {
+                &mut self.chars[n - 1 - i]
+            } else {
+                &mut self.chars[i]
+            };
             ch.position.x = _x;
             ch.position.y = y + ch.metric.baseline;
 
@@ -607,6 +635,7 @@ pub struct Paragraphs {
     extent: Extent,
     segments: Vec<Segment>,
     paras: Vec<ParaText>,
+    wrap: bool,
 }
 
 impl Paragraphs {
@@ -615,9 +644,15 @@ impl Paragraphs {
             segments: Vec::new(),
             paras: Vec::new(),
             extent: Extent::new(),
+            wrap: true,
         }
     }
 
+    //lines only break where the text does when `wrap` is false
+    pub fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+    }
+
     pub fn get_extent(&mut self) -> Extent {
         self.extent.clone()
     }
@@ -674,6 +709,7 @@ impl Paragraphs {
             segments,
             paras: vec![],
             extent: Extent::new(),
+            wrap: true,
         }
     }
 
@@ -764,6 +800,7 @@ impl Paragraphs {
         text_align: &Align,
     ) {
         let mut para_directions = self.init_paras(size, baseline, family);
+        let wrap_w = if self.wrap { w } else { std::f32::INFINITY };
 
         for para in self.paras.iter_mut() {
             let line_directions = para_directions.remove(0);
@@ -772,16 +809,17 @@ impl Paragraphs {
             }
 
             if para.rtl {
-                para.shape_rtl(line_directions, w);
+                para.shape_rtl(line_directions, wrap_w);
             } else {
-                para.shape_ltr(line_directions, w);
+                para.shape_ltr(line_directions, wrap_w);
             }
         }
 
         self.position(x, y, w, h, size, text_align);
     }
 
-    fn position(&mut self, x: f32, y: f32, w: f32, h: f32, size: f32, text_align: &Align) {
+    //places the shaped lines again, such as to scroll them sideways
+    pub fn position(&mut self, x: f32, y: f32, w: f32, h: f32, size: f32, text_align: &Align) {
         let mut _y = y;
         let mut min_x = x + w;
         let mut min_y = y + h;
@@ -850,6 +888,120 @@ impl Paragraphs {
         ret
     }
 
+    //the line holding the char at `index` and whether its paragraph runs
+    //right to left
+    fn find_line(&self, index: usize) -> Option<(&ParaLine, bool)> {
+        for para in self.paras.iter() {
+            for line in para.lines.iter() {
+                for segment in line.segments.iter() {
+                    if segment._ref.chars.iter().any(|ch| ch.index == index) {
+                        return Some((line, para.rtl));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    //The caret stops of the line holding the char at `index` from left to
+    //right, and whether the line runs right to left. A line break only
+    //has the stop before it.
+    pub fn get_caret_stops(&self, index: usize) -> Option<(Vec<CaretStop>, bool)> {
+        let (line, rtl) = self.find_line(index)?;
+        let mut stops = vec![];
+        for segment in line.segments.iter() {
+            for ch in segment._ref.chars.iter() {
+                stops.push(CaretStop {
+                    x: ch.get_caret_x(false),
+                    index: ch.index,
+                    after: false,
+                });
+                if ch.char != '\n' {
+                    stops.push(CaretStop {
+                        x: ch.get_caret_x(true),
+                        index: ch.index,
+                        after: true,
+                    });
+                }
+            }
+        }
+        stops.sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap_or(std::cmp::Ordering::Equal));
+        Some((stops, rtl))
+    }
+
+    //the first and the last index of the chars on the line of `index`
+    pub fn get_line_range(&self, index: usize) -> Option<(usize, usize)> {
+        let (line, _) = self.find_line(index)?;
+        let indices = line
+            .segments
+            .iter()
+            .flat_map(|s| s._ref.chars.iter().map(|ch| ch.index));
+        let first = indices.clone().min()?;
+        let last = indices.max()?;
+        Some((first, last))
+    }
+
+    //Rectangles behind the chars from index `start` up to `end`, one for
+    //every run of chars that sit next to each other on a line, so a line
+    //mixing directions can take several.
+    pub fn get_selection_rects(&self, start: usize, end: usize) -> Vec<Extent> {
+        let mut rects = vec![];
+        for para in self.paras.iter() {
+            for line in para.lines.iter() {
+                let mut line_rects: Vec<Extent> = vec![];
+                for segment in line.segments.iter() {
+                    for ch in segment._ref.chars.iter() {
+                        let x = ch.position.x;
+                        let w = ch.metric.advance.x;
+                        if ch.index < start || ch.index >= end || w <= 0. {
+                            continue;
+                        }
+                        let joined = line_rects
+                            .iter_mut()
+                            .find(|r| (r.x + r.w - x).abs() < 0.5 || (x + w - r.x).abs() < 0.5);
+                        match joined {
+                            Some(r) => {
+                                let right = (r.x + r.w).max(x + w);
+                                r.x = r.x.min(x);
+                                r.w = right - r.x;
+                            }
+                            None => line_rects.push(Extent {
+                                x,
+                                y: line.extent.y,
+                                w,
+                                h: line.extent.h,
+                                dpi: 0.,
+                            }),
+                        }
+                    }
+                }
+                rects.append(&mut line_rects);
+            }
+        }
+        rects
+    }
+
+    //the glyphs of the chars from index `start` up to `end`, such as to
+    //draw them in a color of their own
+    pub fn get_glyphs_in(&self, start: usize, end: usize) -> Vec<GlyphInstance> {
+        let mut arr = vec![];
+        for para in self.paras.iter() {
+            for line in para.lines.iter() {
+                for segment in line.segments.iter() {
+                    for ch in segment._ref.chars.iter() {
+                        if ch.index >= start && ch.index < end {
+                            arr.push(GlyphInstance {
+                                index: ch.glyph,
+                                point: LayoutPoint::new(ch.position.x, ch.position.y),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        arr
+    }
+
     pub fn glyphs(&self) -> Vec<GlyphInstance> {
         let mut arr = vec![];
         for para in self.paras.iter() {
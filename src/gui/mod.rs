@@ -1,4 +1,9 @@
+pub mod clipboard;
+pub mod css;
 pub mod font;
+pub mod overlay;
 pub mod properties;
 mod script;
+pub mod style;
+pub mod theme;
 pub mod window;
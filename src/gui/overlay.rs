@@ -0,0 +1,343 @@
+use std::sync::{Arc, Mutex};
+
+use webrender::api::*;
+
+use crate::elements::{validate_element, Element, PrimitiveEvent};
+use crate::gui::font;
+use crate::gui::properties;
+use crate::gui::window;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Placement {
+    Below,
+    Above,
+    Left,
+    Right,
+}
+
+//Returned by `open`. Cloning it gives another handle to the same popup.
+#[derive(Clone, Debug)]
+pub struct PopupHandle {
+    open: Arc<Mutex<bool>>,
+}
+
+impl PopupHandle {
+    pub fn close(&self) {
+        if let Ok(mut open) = self.open.lock() {
+            *open = false;
+        }
+    }
+
+    //false once the popup is closed or dismissed
+    pub fn is_open(&self) -> bool {
+        match self.open.lock() {
+            Ok(open) => *open,
+            Err(_) => false,
+        }
+    }
+}
+
+struct Popup {
+    content: Arc<Mutex<dyn Element>>,
+    anchor: properties::Extent,
+    placement: Placement,
+    dismissable: bool,
+    handle: PopupHandle,
+    //first and last element id used by the content in the last render
+    ids: (u64, u64),
+}
+
+lazy_static! {
+    //popups with the window they were opened in, None outside of a tick
+    static ref PENDING: Mutex<Vec<(Option<u64>, Popup)>> = Mutex::new(vec![]);
+}
+
+//Opens `content` in the overlay of the window whose events are being
+//handled. `anchor` is in window coordinates, usually the bounds of the
+//element opening the popup. A dismissable popup closes on a click
+//outside of it or on Escape.
+pub fn open(
+    content: Arc<Mutex<dyn Element>>,
+    anchor: properties::Extent,
+    placement: Placement,
+    dismissable: bool,
+) -> PopupHandle {
+    let handle = PopupHandle {
+        open: Arc::new(Mutex::new(true)),
+    };
+    if let Ok(mut pending) = PENDING.lock() {
+        pending.push((
+            window::current(),
+            Popup {
+                content,
+                anchor,
+                placement,
+                dismissable,
+                handle: handle.clone(),
+                ids: (0, 0),
+            },
+        ));
+    }
+    handle
+}
+
+//top left of a `w` by `h` popup next to `anchor`, flipped to the other
+//side when it does not fit and then shifted in to the window
+fn position(
+    anchor: &properties::Extent,
+    w: f32,
+    h: f32,
+    placement: &Placement,
+    win_w: f32,
+    win_h: f32,
+) -> (f32, f32) {
+    let below = anchor.y + anchor.h;
+    let above = anchor.y - h;
+    let right = anchor.x + anchor.w;
+    let left = anchor.x - w;
+
+    let (x, y) = match placement {
+        Placement::Below => (
+            anchor.x,
+            if below + h > win_h && above >= 0.0 {
+                above
+            } else {
+                below
+            },
+        ),
+        Placement::Above => (
+            anchor.x,
+            if above < 0.0 && below + h <= win_h {
+                below
+            } else {
+                above
+            },
+        ),
+        Placement::Right => (
+            if right + w > win_w && left >= 0.0 {
+                left
+            } else {
+                right
+            },
+            anchor.y,
+        ),
+        Placement::Left => (
+            if left < 0.0 && right + w <= win_w {
+                right
+            } else {
+                left
+            },
+            anchor.y,
+        ),
+    };
+
+    (x.min(win_w - w).max(0.0), y.min(win_h - h).max(0.0))
+}
+
+//size of the popup content. Stretch follows the anchor so a dropdown
+//can be as wide as the element that opened it.
+fn size(unit: properties::Unit, natural: f32, anchor: f32, window: f32) -> f32 {
+    match unit {
+        properties::Unit::Pixel(_p) => _p,
+        properties::Unit::Stretch(_s) => _s * anchor,
+        properties::Unit::Extent => window,
+        _ => {
+            if natural.is_nan() || natural.is_infinite() {
+                0.0
+            } else {
+                natural
+            }
+        }
+    }
+}
+
+//The popups of a single window, painted above everything else.
+pub struct Overlay {
+    popups: Vec<Popup>,
+    swallow_release: bool,
+}
+
+impl Overlay {
+    pub fn new() -> Overlay {
+        Overlay {
+            popups: vec![],
+            swallow_release: false,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.popups.is_empty()
+    }
+
+    //Takes popups opened in `window` since the last call and drops closed
+    //ones. A popup opened outside of any window's tick goes to the first
+    //window that takes it.
+    pub fn update(&mut self, window: u64) {
+        if let Ok(mut pending) = PENDING.lock() {
+            let mut i = 0;
+            while i < pending.len() {
+                match pending[i].0 {
+                    Some(w) if w != window => i += 1,
+                    _ => self.popups.push(pending.remove(i).1),
+                }
+            }
+        }
+        self.popups.retain(|p| p.handle.is_open());
+    }
+
+    pub fn render(
+        &mut self,
+        api: &RenderApi,
+        builder: &mut DisplayListBuilder,
+        font_store: &mut font::FontStore,
+        gen: &mut properties::IdGenerator,
+        win_w: f32,
+        win_h: f32,
+        dpi: f32,
+    ) {
+        for popup in self.popups.iter_mut() {
+            let first = gen.last() + 1;
+            match popup.content.lock() {
+                Ok(ref mut elm) => {
+                    let raw = elm.get_properties();
+                    let props = raw.resolved(win_w, win_h);
+                    let bounds = elm.get_bounds();
+                    let w = size(props.get_width(), bounds.w, popup.anchor.w, win_w).min(win_w);
+                    let h = size(props.get_height(), bounds.h, popup.anchor.h, win_h).min(win_h);
+                    let (x, y) = position(&popup.anchor, w, h, &popup.placement, win_w, win_h);
+
+                    //percentages are relative to the window
+                    let w = if raw.get_width().is_relative() {
+                        win_w
+                    } else {
+                        w
+                    };
+                    let h = if raw.get_height().is_relative() {
+                        win_h
+                    } else {
+                        h
+                    };
+
+                    validate_element(&**elm);
+                    elm.render(
+                        api,
+                        builder,
+                        properties::Extent { x, y, w, h, dpi },
+                        font_store,
+                        None,
+                        gen,
+                    );
+                }
+                Err(_err_str) => panic!("unable to lock element : {}", _err_str),
+            }
+            popup.ids = (first, gen.last());
+        }
+    }
+
+    fn owner(&self, tag: &ItemTag) -> Option<usize> {
+        self.popups
+            .iter()
+            .position(|p| tag.0 >= p.ids.0 && tag.0 <= p.ids.1)
+    }
+
+    //the topmost popup under the cursor and the tags that belong to it
+    fn route(&self, tags: &[ItemTag]) -> Option<(usize, Vec<ItemTag>)> {
+        let top = tags.iter().filter_map(|t| self.owner(t)).max()?;
+        let ids = tags
+            .iter()
+            .filter(|t| self.owner(t) == Some(top))
+            .cloned()
+            .collect();
+        Some((top, ids))
+    }
+
+    //tags that are not covered by any popup
+    pub fn root_tags(&self, tags: &[ItemTag]) -> Vec<ItemTag> {
+        tags.iter()
+            .filter(|t| self.owner(t).is_none())
+            .cloned()
+            .collect()
+    }
+
+    //closes the topmost dismissable popup, false if there is none
+    pub fn close_top(&mut self) -> bool {
+        match self.popups.iter().rposition(|p| p.dismissable) {
+            Some(i) => {
+                let p = self.popups.remove(i);
+                p.handle.close();
+                true
+            }
+            None => false,
+        }
+    }
+
+    //closes every dismissable popup, false if there was none
+    fn dismiss(&mut self) -> bool {
+        let mut dismissed = false;
+        for p in self.popups.iter().filter(|p| p.dismissable) {
+            p.handle.close();
+            dismissed = true;
+        }
+        self.popups.retain(|p| !p.dismissable);
+        dismissed
+    }
+
+    //Sends a mouse event to the popup under the cursor. Returns None if
+    //the event was handled by the overlay, otherwise the tags for the
+    //rest of the window.
+    pub fn on_pointer_event(
+        &mut self,
+        tags: &[ItemTag],
+        e: &PrimitiveEvent,
+    ) -> Option<Vec<ItemTag>> {
+        if let Some((i, ids)) = self.route(tags) {
+            let content = self.popups[i].content.clone();
+            content.lock().unwrap().on_primitive_event(&ids, e.clone());
+            return None;
+        }
+
+        if let PrimitiveEvent::Button(_, _, ref s, _) = e {
+            if *s == properties::ButtonState::Pressed && self.dismiss() {
+                self.swallow_release = true;
+                return None;
+            }
+            if *s == properties::ButtonState::Released && self.swallow_release {
+                self.swallow_release = false;
+                return None;
+            }
+        }
+
+        Some(self.root_tags(tags))
+    }
+
+    //Keyboard events go to the popups first, topmost first, until one
+    //handles them. Hover changes go to every popup so none under another
+    //is left highlighted.
+    pub fn on_event(&mut self, e: &PrimitiveEvent) -> bool {
+        let hover = match e {
+            PrimitiveEvent::HoverBegin(_) | PrimitiveEvent::HoverEnd(_) => true,
+            _ => false,
+        };
+        let mut handled = false;
+        for popup in self.popups.iter().rev() {
+            if popup
+                .content
+                .lock()
+                .unwrap()
+                .on_primitive_event(&[], e.clone())
+            {
+                handled = true;
+                if !hover {
+                    break;
+                }
+            }
+        }
+        handled
+    }
+}
+
+impl Default for Overlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
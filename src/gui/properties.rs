@@ -4,6 +4,9 @@ use std::mem;
 use std::sync::{Arc, Mutex};
 
 use webrender::api::ColorF;
+
+use crate::gui::style::StyleNode;
+use crate::gui::theme::Theme;
 //use webrender::api::DeviceSize
 
 #[derive(Clone, Debug, PartialEq)]
@@ -45,6 +48,52 @@ pub enum Unit {
     Extent,
     Pixel(f32),
     Stretch(f32),
+    Percent(f32), //of the parent's extent
+    Em(f32),      //of the element's font size
+    Rem(f32),     //of the root element's font size
+    Vw(f32),      //percent of the window width
+    Vh(f32),      //percent of the window height
+    //sum of the terms, 100% - 40px is Calc(vec![Percent(100.0), Pixel(-40.0)])
+    Calc(Vec<Unit>),
+}
+
+impl Unit {
+    //pixels of a length against a `parent` extent and a `font` size. None
+    //for Natural, Extent and Stretch which are up to the container.
+    pub fn length(&self, parent: f32, font: f32) -> Option<f32> {
+        match self {
+            Unit::Pixel(_p) => Some(*_p),
+            Unit::Percent(_p) => Some(_p * parent / 100.0),
+            Unit::Em(_e) => Some(_e * font),
+            Unit::Rem(_r) => Some(_r * Viewport::current().rem),
+            Unit::Vw(_v) => Some(_v * Viewport::current().w / 100.0),
+            Unit::Vh(_v) => Some(_v * Viewport::current().h / 100.0),
+            Unit::Calc(terms) => Some(
+                terms
+                    .iter()
+                    .filter_map(|t| t.length(parent, font))
+                    .sum(),
+            ),
+            _ => None,
+        }
+    }
+
+    //the same unit with any length turned in to Pixel
+    pub fn resolve(&self, parent: f32, font: f32) -> Unit {
+        match self.length(parent, font) {
+            Some(_p) => Unit::Pixel(_p),
+            None => self.clone(),
+        }
+    }
+
+    //lengths that depend on the extent the element is given
+    pub fn is_relative(&self) -> bool {
+        match self {
+            Unit::Percent(_) => true,
+            Unit::Calc(terms) => terms.iter().any(|t| t.is_relative()),
+            _ => false,
+        }
+    }
 }
 impl PartialEq for Unit {
     fn eq(&self, other: &Unit) -> bool {
@@ -65,6 +114,25 @@ pub enum Align {
     Right,
 }
 
+//placement of text between the top and bottom of its element
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum VerticalAlign {
+    Top,
+    Center,
+    Bottom,
+    Baseline, //first baseline on the middle of the element
+}
+
+//placement of a child on the cross axis of its container
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CrossAlign {
+    Auto, //use the container's setting
+    Start,
+    Center,
+    End,
+    Stretch,
+}
+
 #[derive(Clone, Debug)]
 pub enum Property {
     Size(i32), //in pixels
@@ -77,6 +145,13 @@ pub enum Property {
     Bottom(Unit), //in pixels or stretches
     MinWidth(Unit),
     MinHeight(Unit),
+    MaxWidth(Unit),
+    MaxHeight(Unit),
+    FlexGrow(f32),
+    FlexShrink(f32),
+    FlexBasis(Unit), //Natural uses the Width/Height of the element
+    AlignSelf(CrossAlign),
+    ZIndex(i32), //higher is painted on top and hit first
     Color(ColorF),
     BgColor(ColorF),
     HoverColor(ColorF),
@@ -87,7 +162,19 @@ pub enum Property {
     ActiveBgColor(ColorF),
     DisabledColor(ColorF),
     DisabledBgColor(ColorF),
+    InvalidColor(ColorF),
+    InvalidBgColor(ColorF),
+    SelectionBgColor(ColorF), //behind selected text
+    CaretColor(ColorF),
+    CaretWidth(f32), //in pixels
+    GutterColor(ColorF), //line numbers
+    GutterBgColor(ColorF),
+    LineHighlightColor(ColorF), //behind the line the caret is on
+    BracketMatchColor(ColorF),  //behind a bracket and the one it matches
     TextAlign(Align),
+    VerticalAlign(VerticalAlign),
+    Id(String),
+    Class(String), //names separated by whitespace
 }
 
 lazy_static! {
@@ -101,6 +188,13 @@ lazy_static! {
     pub static ref BOTTOM: Property = Property::Bottom(Unit::Stretch(0.0));
     pub static ref MIN_WIDTH: Property = Property::MinWidth(Unit::Pixel(0.0));
     pub static ref MIN_HEIGHT: Property = Property::MinHeight(Unit::Pixel(0.0));
+    pub static ref MAX_WIDTH: Property = Property::MaxWidth(Unit::Pixel(std::f32::INFINITY));
+    pub static ref MAX_HEIGHT: Property = Property::MaxHeight(Unit::Pixel(std::f32::INFINITY));
+    pub static ref FLEX_GROW: Property = Property::FlexGrow(0.0);
+    pub static ref FLEX_SHRINK: Property = Property::FlexShrink(1.0);
+    pub static ref FLEX_BASIS: Property = Property::FlexBasis(Unit::Natural);
+    pub static ref ALIGN_SELF: Property = Property::AlignSelf(CrossAlign::Auto);
+    pub static ref Z_INDEX: Property = Property::ZIndex(0);
     pub static ref COLOR: Property = Property::Color(ColorF {
         r: 0.2,
         g: 0.2,
@@ -161,7 +255,118 @@ lazy_static! {
         b: 0.8,
         a: 1.0,
     });
+    pub static ref INVALID_COLOR: Property = Property::InvalidColor(ColorF {
+        r: 0.8,
+        g: 0.0,
+        b: 0.0,
+        a: 1.0,
+    });
+    pub static ref INVALID_BG_COLOR: Property = Property::InvalidBgColor(ColorF {
+        r: 1.0,
+        g: 0.9,
+        b: 0.9,
+        a: 1.0,
+    });
+    pub static ref SELECTION_BG_COLOR: Property = Property::SelectionBgColor(ColorF {
+        r: 0.6,
+        g: 0.8,
+        b: 1.0,
+        a: 1.0,
+    });
+    pub static ref CARET_COLOR: Property = Property::CaretColor(ColorF {
+        r: 0.0,
+        g: 0.0,
+        b: 0.0,
+        a: 1.0,
+    });
+    pub static ref CARET_WIDTH: Property = Property::CaretWidth(1.0);
+    pub static ref GUTTER_COLOR: Property = Property::GutterColor(ColorF {
+        r: 0.5,
+        g: 0.5,
+        b: 0.5,
+        a: 1.0,
+    });
+    pub static ref GUTTER_BG_COLOR: Property = Property::GutterBgColor(ColorF {
+        r: 0.95,
+        g: 0.95,
+        b: 0.95,
+        a: 1.0,
+    });
+    pub static ref LINE_HIGHLIGHT_COLOR: Property = Property::LineHighlightColor(ColorF {
+        r: 0.95,
+        g: 0.95,
+        b: 0.85,
+        a: 1.0,
+    });
+    pub static ref BRACKET_MATCH_COLOR: Property = Property::BracketMatchColor(ColorF {
+        r: 0.8,
+        g: 0.9,
+        b: 0.8,
+        a: 1.0,
+    });
     pub static ref TEXT_ALIGN: Property = Property::TextAlign(Align::Left);
+    pub static ref VERTICAL_ALIGN: Property = Property::VerticalAlign(VerticalAlign::Top);
+    pub static ref ID: Property = Property::Id(String::from(""));
+    pub static ref CLASS: Property = Property::Class(String::from(""));
+}
+
+impl Property {
+    //name of the property for messages
+    pub fn name(&self) -> &'static str {
+        match self {
+            Property::Size(_) => "Size",
+            Property::Family(_) => "Family",
+            Property::Left(_) => "Left",
+            Property::Width(_) => "Width",
+            Property::Right(_) => "Right",
+            Property::Top(_) => "Top",
+            Property::Height(_) => "Height",
+            Property::Bottom(_) => "Bottom",
+            Property::MinWidth(_) => "Min Width",
+            Property::MinHeight(_) => "Min Height",
+            Property::MaxWidth(_) => "Max Width",
+            Property::MaxHeight(_) => "Max Height",
+            Property::FlexGrow(_) => "Flex Grow",
+            Property::FlexShrink(_) => "Flex Shrink",
+            Property::FlexBasis(_) => "Flex Basis",
+            Property::AlignSelf(_) => "Align Self",
+            Property::ZIndex(_) => "Z Index",
+            Property::Color(_) => "Color",
+            Property::BgColor(_) => "Background Color",
+            Property::FocusColor(_) => "Focus Color",
+            Property::FocusBgColor(_) => "Focus Background Color",
+            Property::HoverColor(_) => "Hover Color",
+            Property::HoverBgColor(_) => "Hover Background Color",
+            Property::ActiveColor(_) => "Active Color",
+            Property::ActiveBgColor(_) => "Active Background Color",
+            Property::DisabledColor(_) => "Disabled Color",
+            Property::DisabledBgColor(_) => "Disabled Background Color",
+            Property::InvalidColor(_) => "Invalid Color",
+            Property::InvalidBgColor(_) => "Invalid Background Color",
+            Property::SelectionBgColor(_) => "Selection Background Color",
+            Property::CaretColor(_) => "Caret Color",
+            Property::CaretWidth(_) => "Caret Width",
+            Property::GutterColor(_) => "Gutter Color",
+            Property::GutterBgColor(_) => "Gutter Background Color",
+            Property::LineHighlightColor(_) => "Line Highlight Color",
+            Property::BracketMatchColor(_) => "Bracket Match Color",
+            Property::TextAlign(_) => "Text Align",
+            Property::VerticalAlign(_) => "Vertical Align",
+            Property::Id(_) => "Id",
+            Property::Class(_) => "Class",
+        }
+    }
+
+    //properties a child takes from its parent unless it sets them itself
+    pub fn inherits(&self) -> bool {
+        match self {
+            Property::Size(_) => true,
+            Property::Family(_) => true,
+            Property::Color(_) => true,
+            Property::TextAlign(_) => true,
+            _ => false,
+        }
+    }
 }
 
 impl PartialEq for Property {
@@ -177,206 +382,276 @@ impl Hash for Property {
     }
 }
 
+lazy_static! {
+    //what a property falls back to when it is missing
+    static ref DEFAULTS: Properties = {
+        let mut props = Properties::new();
+        props.default();
+        props
+    };
+    //the element and property of every report so far
+    static ref REPORTED: Mutex<HashSet<(String, &'static str)>> = Mutex::new(HashSet::new());
+}
+
+//A getter that falls back to the theme and then to the defaults when the
+//property is missing, and a try_ variant that returns None instead.
+macro_rules! getter {
+    ($get:ident, $try_get:ident, $key:ident, $variant:ident, $t:ty) => {
+        pub fn $try_get(&self) -> Option<$t> {
+            match self.get(&$key) {
+                Some(Property::$variant(x)) => Some(x.clone()),
+                _ => None,
+            }
+        }
+
+        pub fn $get(&self) -> $t {
+            if let Some(x) = self.$try_get() {
+                return x;
+            }
+            if let Some(Property::$variant(x)) = Theme::current().get_default("*", &$key) {
+                return x;
+            }
+            match DEFAULTS.$try_get() {
+                Some(x) => x,
+                None => panic!("{} has no default", $key.name()),
+            }
+        }
+    };
+}
+
+//The keys in `explicit` were given with `set` and are passed on to
+//children that inherit them. Values given with `set_default` are only
+//used when the parent has nothing to pass on.
 #[derive(Clone, Debug, Default)]
-pub struct Properties(HashSet<Property>);
+pub struct Properties {
+    values: HashSet<Property>,
+    explicit: HashSet<Property>,
+    inherit: HashSet<Property>, //always take the parent's value
+    path: Vec<StyleNode>,       //elements these were computed for, root first
+}
 
 impl Properties {
     pub fn new() -> Properties {
-        Properties(HashSet::new())
+        Properties {
+            values: HashSet::new(),
+            explicit: HashSet::new(),
+            inherit: HashSet::new(),
+            path: vec![],
+        }
     }
 
     pub fn default(&mut self) -> &mut Properties {
         if cfg!(target_os = "linux")
         {
-            self.set(Property::Family(String::from("FreeMono")));
+            self.set_default(Property::Family(String::from("FreeMono")));
         }
         if cfg!(target_os = "windows") || cfg!(target_os = "macos")
         {
-            self.set(Property::Family(String::from("Arial")));
+            self.set_default(Property::Family(String::from("Arial")));
         }
-        self.set(Property::Size(16))
-            .set(Property::Left(Unit::Stretch(0.0)))
-            .set(Property::Width(Unit::Stretch(1.0)))
-            .set(Property::Right(Unit::Stretch(0.0)))
-            .set(Property::Top(Unit::Stretch(0.0)))
-            .set(Property::Height(Unit::Stretch(1.0)))
-            .set(Property::Bottom(Unit::Stretch(0.0)))
-            .set(Property::MinWidth(Unit::Pixel(0.0)))
-            .set(Property::MinHeight(Unit::Pixel(0.0)))
-            .set(Property::Color(ColorF::new(0.8, 0.8, 0.8, 1.0)))
-            .set(Property::BgColor(ColorF::new(1.0, 1.0, 1.0, 0.0)))
-            .set(Property::FocusColor(ColorF::new(1.0, 1.0, 1.0, 1.0)))
-            .set(Property::FocusBgColor(ColorF::new(0.0, 0.0, 0.0, 0.0)))
-            .set(Property::HoverColor(ColorF::new(0.9, 0.9, 0.9, 1.0)))
-            .set(Property::HoverBgColor(ColorF::new(0.0, 0.0, 0.0, 0.0)))
-            .set(Property::DisabledColor(ColorF::new(0.5, 0.5, 0.5, 1.0)))
-            .set(Property::DisabledBgColor(ColorF::new(0.0, 0.0, 0.0, 0.0)))
-            .set(Property::TextAlign(Align::Left))
+        self.set_default(Property::Size(16))
+            .set_default(Property::Left(Unit::Stretch(0.0)))
+            .set_default(Property::Width(Unit::Stretch(1.0)))
+            .set_default(Property::Right(Unit::Stretch(0.0)))
+            .set_default(Property::Top(Unit::Stretch(0.0)))
+            .set_default(Property::Height(Unit::Stretch(1.0)))
+            .set_default(Property::Bottom(Unit::Stretch(0.0)))
+            .set_default(Property::MinWidth(Unit::Pixel(0.0)))
+            .set_default(Property::MinHeight(Unit::Pixel(0.0)))
+            .set_default(Property::MaxWidth(Unit::Pixel(std::f32::INFINITY)))
+            .set_default(Property::MaxHeight(Unit::Pixel(std::f32::INFINITY)))
+            .set_default(Property::FlexGrow(0.0))
+            .set_default(Property::FlexShrink(1.0))
+            .set_default(Property::FlexBasis(Unit::Natural))
+            .set_default(Property::AlignSelf(CrossAlign::Auto))
+            .set_default(Property::ZIndex(0))
+            .set_default(Property::Color(ColorF::new(0.8, 0.8, 0.8, 1.0)))
+            .set_default(Property::BgColor(ColorF::new(1.0, 1.0, 1.0, 0.0)))
+            .set_default(Property::FocusColor(ColorF::new(1.0, 1.0, 1.0, 1.0)))
+            .set_default(Property::FocusBgColor(ColorF::new(0.0, 0.0, 0.0, 0.0)))
+            .set_default(Property::HoverColor(ColorF::new(0.9, 0.9, 0.9, 1.0)))
+            .set_default(Property::HoverBgColor(ColorF::new(0.0, 0.0, 0.0, 0.0)))
+            .set_default(Property::ActiveColor(ColorF::new(1.0, 1.0, 1.0, 1.0)))
+            .set_default(Property::ActiveBgColor(ColorF::new(0.0, 0.0, 0.0, 0.0)))
+            .set_default(Property::DisabledColor(ColorF::new(0.5, 0.5, 0.5, 1.0)))
+            .set_default(Property::DisabledBgColor(ColorF::new(0.0, 0.0, 0.0, 0.0)))
+            .set_default(Property::InvalidColor(ColorF::new(0.8, 0.0, 0.0, 1.0)))
+            .set_default(Property::InvalidBgColor(ColorF::new(0.0, 0.0, 0.0, 0.0)))
+            .set_default(Property::SelectionBgColor(ColorF::new(0.6, 0.8, 1.0, 1.0)))
+            .set_default(Property::CaretColor(ColorF::new(1.0, 1.0, 1.0, 1.0)))
+            .set_default(Property::CaretWidth(1.0))
+            .set_default(Property::GutterColor(ColorF::new(0.5, 0.5, 0.5, 1.0)))
+            .set_default(Property::GutterBgColor(ColorF::new(0.0, 0.0, 0.0, 0.0)))
+            .set_default(Property::LineHighlightColor(ColorF::new(0.5, 0.5, 0.5, 0.1)))
+            .set_default(Property::BracketMatchColor(ColorF::new(0.5, 0.5, 0.5, 0.3)))
+            .set_default(Property::TextAlign(Align::Left))
+            .set_default(Property::VerticalAlign(VerticalAlign::Top))
+            .set_default(Property::Id(String::from("")))
+            .set_default(Property::Class(String::from("")))
     }
 
     pub fn set(&mut self, property: Property) -> &mut Properties {
+        self.inherit.remove(&property);
+        self.explicit.replace(property.clone());
+        self.set_default(property)
+    }
+
+    //a value that does not override what the parent passes on
+    pub fn set_default(&mut self, property: Property) -> &mut Properties {
         {
-            let x = &mut self.0;
+            let x = &mut self.values;
             x.replace(property);
         }
         self
     }
 
-    pub fn get(&self, property: &Property) -> Option<&Property> {
-        self.0.get(property)
-    }
-
-    pub fn get_size(&self) -> i32 {
-        if let Some(Property::Size(x)) = self.get(&SIZE) {
-            *x
-        } else {
-            panic!("Size not found")
-        }
-    }
-
-    pub fn get_family(&self) -> String {
-        if let Some(Property::Family(x)) = self.get(&FAMILY) {
-            x.clone()
-        } else {
-            panic!("Family not found")
-        }
-    }
-
-    pub fn get_left(&self) -> Unit {
-        if let Some(Property::Left(x)) = self.get(&LEFT) {
-            x.clone()
-        } else {
-            panic!("Left not found")
-        }
-    }
-
-    pub fn get_width(&self) -> Unit {
-        if let Some(Property::Width(x)) = self.get(&WIDTH) {
-            x.clone()
-        } else {
-            panic!("Width not found")
-        }
-    }
-
-    pub fn get_right(&self) -> Unit {
-        if let Some(Property::Right(x)) = self.get(&RIGHT) {
-            x.clone()
-        } else {
-            panic!("Right not found")
-        }
-    }
-
-    pub fn get_top(&self) -> Unit {
-        if let Some(Property::Top(x)) = self.get(&TOP) {
-            x.clone()
-        } else {
-            panic!("Top not found")
-        }
-    }
-
-    pub fn get_height(&self) -> Unit {
-        if let Some(Property::Height(x)) = self.get(&HEIGHT) {
-            x.clone()
-        } else {
-            panic!("Height not found")
-        }
-    }
-
-    pub fn get_bottom(&self) -> Unit {
-        if let Some(Property::Bottom(x)) = self.get(&BOTTOM) {
-            x.clone()
-        } else {
-            panic!("Bottom not found")
-        }
+    //take the parent's value of `key` even if it does not inherit
+    pub fn inherit(&mut self, key: &Property) -> &mut Properties {
+        self.explicit.remove(key);
+        self.inherit.replace(key.clone());
+        self
     }
 
-    pub fn get_min_width(&self) -> Unit {
-        if let Some(Property::MinWidth(x)) = self.get(&MIN_WIDTH) {
-            x.clone()
-        } else {
-            panic!("Min Width not found")
+    //go back to the default value of `key` and stop inheriting it
+    pub fn initial(&mut self, key: &Property) -> &mut Properties {
+        let mut defaults = Properties::new();
+        defaults.default();
+        match defaults.get(key) {
+            Some(_p) => {
+                let _p = _p.clone();
+                self.set(_p)
+            }
+            None => self,
         }
     }
 
-    pub fn get_min_height(&self) -> Unit {
-        if let Some(Property::MinHeight(x)) = self.get(&MIN_HEIGHT) {
-            x.clone()
-        } else {
-            panic!("Min Height not found")
-        }
+    pub fn get(&self, property: &Property) -> Option<&Property> {
+        self.values.get(property)
     }
 
-    pub fn get_color(&self) -> ColorF {
-        if let Some(Property::Color(x)) = self.get(&COLOR) {
-            *x
-        } else {
-            panic!("Color not found")
-        }
+    //whether `key` was given with `set` or `inherit`
+    pub fn is_set(&self, key: &Property) -> bool {
+        self.explicit.contains(key) || self.inherit.contains(key)
     }
 
-    pub fn get_bg_color(&self) -> ColorF {
-        if let Some(Property::BgColor(x)) = self.get(&BG_COLOR) {
-            *x
-        } else {
-            panic!("Background Color not found")
-        }
+    pub fn get_path(&self) -> &[StyleNode] {
+        &self.path
     }
 
-    pub fn get_focus_color(&self) -> ColorF {
-        if let Some(Property::FocusColor(x)) = self.get(&FOCUS_COLOR) {
-            *x
-        } else {
-            panic!("Focus Color not found")
-        }
+    pub fn set_path(&mut self, path: Vec<StyleNode>) {
+        self.path = path;
     }
 
-    pub fn get_focus_bg_color(&self) -> ColorF {
-        if let Some(Property::FocusBgColor(x)) = self.get(&FOCUS_BG_COLOR) {
-            *x
-        } else {
-            panic!("Focus Background Color not found")
-        }
+    //names of the properties that are missing and get their default
+    pub fn missing(&self) -> Vec<&'static str> {
+        let mut names: Vec<&'static str> = DEFAULTS
+            .values
+            .iter()
+            .filter(|p| !self.values.contains(*p))
+            .map(|p| p.name())
+            .collect();
+        names.sort();
+        names
     }
 
-    pub fn get_hover_color(&self) -> ColorF {
-        if let Some(Property::HoverColor(x)) = self.get(&HOVER_COLOR) {
-            *x
-        } else {
-            panic!("Hover Color not found")
+    //In debug builds prints the properties `element`, its kind and id or
+    //ext id, is missing. Each property of an element is reported once.
+    pub fn validate(&self, element: &str) {
+        if !cfg!(debug_assertions) {
+            return;
         }
-    }
-
-    pub fn get_hover_bg_color(&self) -> ColorF {
-        if let Some(Property::HoverBgColor(x)) = self.get(&HOVER_BG_COLOR) {
-            *x
-        } else {
-            panic!("Hover Background Color not found")
+        let mut missing = self.missing();
+        if missing.is_empty() {
+            return;
         }
-    }
-
-    pub fn get_disabled_color(&self) -> ColorF {
-        if let Some(Property::DisabledColor(x)) = self.get(&DISABLED_COLOR) {
-            *x
-        } else {
-            panic!("Disabled Color not found")
+        if let Ok(mut reported) = REPORTED.lock() {
+            missing.retain(|name| reported.insert((element.to_string(), name)));
         }
-    }
-
-    pub fn get_disabled_bg_color(&self) -> ColorF {
-        if let Some(Property::DisabledBgColor(x)) = self.get(&DISABLED_BG_COLOR) {
-            *x
-        } else {
-            panic!("Disabled Background Color not found")
+        if !missing.is_empty() {
+            eprintln!("{} is missing {}", element, missing.join(", "));
         }
     }
 
-    pub fn get_text_align(&self) -> Align {
-        if let Some(Property::TextAlign(x)) = self.get(&TEXT_ALIGN) {
-            x.clone()
-        } else {
-            panic!("Text Align not found")
+    //the values an element renders with, given the values of its parent
+    pub fn cascade(&self, parent: &Option<Arc<Properties>>) -> Properties {
+        let mut props = self.clone();
+        if let Some(parent) = parent {
+            for key in parent.explicit.iter() {
+                if key.inherits() && !self.explicit.contains(key) {
+                    if let Some(_p) = parent.get(key) {
+                        props.set(_p.clone());
+                    }
+                }
+            }
+            for key in self.inherit.iter() {
+                if let Some(_p) = parent.get(key) {
+                    props.set(_p.clone());
+                }
+            }
         }
-    }
+        props
+    }
+
+    //a copy with every length resolved to Pixel against a parent of `w`
+    //by `h`, so layout only has to deal with Pixel, Natural, Extent and
+    //Stretch
+    pub fn resolved(&self, w: f32, h: f32) -> Properties {
+        let font = self.get_size() as f32;
+        let mut props = self.clone();
+        props
+            .set_default(Property::Left(self.get_left().resolve(w, font)))
+            .set_default(Property::Width(self.get_width().resolve(w, font)))
+            .set_default(Property::Right(self.get_right().resolve(w, font)))
+            .set_default(Property::MinWidth(self.get_min_width().resolve(w, font)))
+            .set_default(Property::MaxWidth(self.get_max_width().resolve(w, font)))
+            .set_default(Property::Top(self.get_top().resolve(h, font)))
+            .set_default(Property::Height(self.get_height().resolve(h, font)))
+            .set_default(Property::Bottom(self.get_bottom().resolve(h, font)))
+            .set_default(Property::MinHeight(self.get_min_height().resolve(h, font)))
+            .set_default(Property::MaxHeight(self.get_max_height().resolve(h, font)));
+        props
+    }
+
+    getter!(get_size, try_get_size, SIZE, Size, i32);
+    getter!(get_family, try_get_family, FAMILY, Family, String);
+    getter!(get_left, try_get_left, LEFT, Left, Unit);
+    getter!(get_width, try_get_width, WIDTH, Width, Unit);
+    getter!(get_right, try_get_right, RIGHT, Right, Unit);
+    getter!(get_top, try_get_top, TOP, Top, Unit);
+    getter!(get_height, try_get_height, HEIGHT, Height, Unit);
+    getter!(get_bottom, try_get_bottom, BOTTOM, Bottom, Unit);
+    getter!(get_min_width, try_get_min_width, MIN_WIDTH, MinWidth, Unit);
+    getter!(get_min_height, try_get_min_height, MIN_HEIGHT, MinHeight, Unit);
+    getter!(get_max_width, try_get_max_width, MAX_WIDTH, MaxWidth, Unit);
+    getter!(get_max_height, try_get_max_height, MAX_HEIGHT, MaxHeight, Unit);
+    getter!(get_flex_grow, try_get_flex_grow, FLEX_GROW, FlexGrow, f32);
+    getter!(get_flex_shrink, try_get_flex_shrink, FLEX_SHRINK, FlexShrink, f32);
+    getter!(get_flex_basis, try_get_flex_basis, FLEX_BASIS, FlexBasis, Unit);
+    getter!(get_align_self, try_get_align_self, ALIGN_SELF, AlignSelf, CrossAlign);
+    getter!(get_z_index, try_get_z_index, Z_INDEX, ZIndex, i32);
+    getter!(get_color, try_get_color, COLOR, Color, ColorF);
+    getter!(get_bg_color, try_get_bg_color, BG_COLOR, BgColor, ColorF);
+    getter!(get_focus_color, try_get_focus_color, FOCUS_COLOR, FocusColor, ColorF);
+    getter!(get_focus_bg_color, try_get_focus_bg_color, FOCUS_BG_COLOR, FocusBgColor, ColorF);
+    getter!(get_hover_color, try_get_hover_color, HOVER_COLOR, HoverColor, ColorF);
+    getter!(get_hover_bg_color, try_get_hover_bg_color, HOVER_BG_COLOR, HoverBgColor, ColorF);
+    getter!(get_active_color, try_get_active_color, ACTIVE_COLOR, ActiveColor, ColorF);
+    getter!(get_active_bg_color, try_get_active_bg_color, ACTIVE_BG_COLOR, ActiveBgColor, ColorF);
+    getter!(get_disabled_color, try_get_disabled_color, DISABLED_COLOR, DisabledColor, ColorF);
+    getter!(get_disabled_bg_color, try_get_disabled_bg_color, DISABLED_BG_COLOR, DisabledBgColor, ColorF);
+    getter!(get_invalid_color, try_get_invalid_color, INVALID_COLOR, InvalidColor, ColorF);
+    getter!(get_invalid_bg_color, try_get_invalid_bg_color, INVALID_BG_COLOR, InvalidBgColor, ColorF);
+    getter!(get_selection_bg_color, try_get_selection_bg_color, SELECTION_BG_COLOR, SelectionBgColor, ColorF);
+    getter!(get_caret_color, try_get_caret_color, CARET_COLOR, CaretColor, ColorF);
+    getter!(get_caret_width, try_get_caret_width, CARET_WIDTH, CaretWidth, f32);
+    getter!(get_gutter_color, try_get_gutter_color, GUTTER_COLOR, GutterColor, ColorF);
+    getter!(get_gutter_bg_color, try_get_gutter_bg_color, GUTTER_BG_COLOR, GutterBgColor, ColorF);
+    getter!(get_line_highlight_color, try_get_line_highlight_color, LINE_HIGHLIGHT_COLOR, LineHighlightColor, ColorF);
+    getter!(get_bracket_match_color, try_get_bracket_match_color, BRACKET_MATCH_COLOR, BracketMatchColor, ColorF);
+    getter!(get_text_align, try_get_text_align, TEXT_ALIGN, TextAlign, Align);
+    getter!(get_vertical_align, try_get_vertical_align, VERTICAL_ALIGN, VerticalAlign, VerticalAlign);
+    getter!(get_id, try_get_id, ID, Id, String);
+    getter!(get_class, try_get_class, CLASS, Class, String);
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -407,6 +682,34 @@ pub enum Button {
     Other,
 }
 
+//size of the window being rendered and the font size of its root
+//element, used by the Vw, Vh and Rem units
+#[derive(Clone, Debug)]
+pub struct Viewport {
+    pub w: f32,
+    pub h: f32,
+    pub rem: f32,
+}
+
+lazy_static! {
+    static ref VIEWPORT: Mutex<Viewport> = Mutex::new(Viewport {
+        w: 0.0,
+        h: 0.0,
+        rem: 16.0,
+    });
+}
+
+impl Viewport {
+    pub fn current() -> Viewport {
+        VIEWPORT.lock().unwrap().clone()
+    }
+
+    //set by the window before it renders its elements
+    pub fn set(v: Viewport) {
+        *VIEWPORT.lock().unwrap() = v;
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct IdGenerator {
     pub next_id: Arc<Mutex<u64>>,
@@ -423,6 +726,10 @@ impl IdGenerator {
         *counter += 1;
         *counter
     }
+    //the id handed out by the last call to get
+    pub fn last(&self) -> u64 {
+        *self.next_id.lock().unwrap()
+    }
     pub fn zero(&mut self) {
         let mut counter = self.next_id.lock().unwrap();
         *counter = 0;
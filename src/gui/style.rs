@@ -0,0 +1,290 @@
+use std::sync::{Arc, Mutex};
+
+use crate::gui::properties::{Properties, Property};
+use crate::gui::theme::Theme;
+
+//state of an element that a selector can ask for
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum State {
+    Hover,
+    Focus,
+    Disabled,
+    Active,
+    Invalid,
+}
+
+impl State {
+    //Elements pick their colors for a state from the state's own
+    //properties, so a rule for a state sets those instead of Color and
+    //BgColor.
+    fn slot(&self, property: &Property) -> Property {
+        match (self, property) {
+            (State::Hover, Property::Color(c)) => Property::HoverColor(*c),
+            (State::Hover, Property::BgColor(c)) => Property::HoverBgColor(*c),
+            (State::Focus, Property::Color(c)) => Property::FocusColor(*c),
+            (State::Focus, Property::BgColor(c)) => Property::FocusBgColor(*c),
+            (State::Disabled, Property::Color(c)) => Property::DisabledColor(*c),
+            (State::Disabled, Property::BgColor(c)) => Property::DisabledBgColor(*c),
+            (State::Active, Property::Color(c)) => Property::ActiveColor(*c),
+            (State::Active, Property::BgColor(c)) => Property::ActiveBgColor(*c),
+            (State::Invalid, Property::Color(c)) => Property::InvalidColor(*c),
+            (State::Invalid, Property::BgColor(c)) => Property::InvalidBgColor(*c),
+            _ => property.clone(),
+        }
+    }
+}
+
+//what selectors are matched against, there is one for every element
+//from the root down to the one being styled
+#[derive(Clone, Debug, PartialEq)]
+pub struct StyleNode {
+    pub kind: String,
+    pub id: String,
+    pub classes: Vec<String>,
+    pub states: Vec<State>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SimpleSelector {
+    Any,
+    Type(String),
+    Class(String),
+    Id(String),
+    State(State),
+}
+
+impl SimpleSelector {
+    fn matches(&self, node: &StyleNode) -> bool {
+        match self {
+            SimpleSelector::Any => true,
+            SimpleSelector::Type(t) => *t == node.kind,
+            SimpleSelector::Class(c) => node.classes.contains(c),
+            SimpleSelector::Id(i) => *i == node.id,
+            SimpleSelector::State(s) => node.states.contains(s),
+        }
+    }
+}
+
+//simple selectors that all have to match the same element, as in
+//`Button.digit:hover`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Compound(pub Vec<SimpleSelector>);
+
+impl Compound {
+    fn matches(&self, node: &StyleNode) -> bool {
+        self.0.iter().all(|s| s.matches(node))
+    }
+
+    //the state a rule ending in this compound is for
+    fn state(&self) -> Option<&State> {
+        self.0.iter().rev().find_map(|s| match s {
+            SimpleSelector::State(state) => Some(state),
+            _ => None,
+        })
+    }
+}
+
+//compounds separated by whitespace, each one matching a descendant of
+//an element the one before matched
+#[derive(Clone, Debug, PartialEq)]
+pub struct Selector(pub Vec<Compound>);
+
+impl Selector {
+    pub fn parse(s: &str) -> Result<Selector, String> {
+        let mut compounds = vec![];
+        for part in s.split_whitespace() {
+            compounds.push(parse_compound(part)?);
+        }
+        if compounds.is_empty() {
+            return Err(String::from("empty selector"));
+        }
+        Ok(Selector(compounds))
+    }
+
+    //ids, then classes and states, then types
+    pub fn specificity(&self) -> (u32, u32, u32) {
+        let mut spec = (0, 0, 0);
+        for s in self.0.iter().flat_map(|c| c.0.iter()) {
+            match s {
+                SimpleSelector::Id(_) => spec.0 += 1,
+                SimpleSelector::Class(_) | SimpleSelector::State(_) => spec.1 += 1,
+                SimpleSelector::Type(_) => spec.2 += 1,
+                SimpleSelector::Any => (),
+            }
+        }
+        spec
+    }
+
+    //`ancestors` go from the root down to the parent of `node`
+    pub fn matches(&self, node: &StyleNode, ancestors: &[StyleNode]) -> bool {
+        let (last, rest) = match self.0.split_last() {
+            Some(_s) => _s,
+            None => return false,
+        };
+        if !last.matches(node) {
+            return false;
+        }
+        let mut rest = rest.iter().rev().peekable();
+        for ancestor in ancestors.iter().rev() {
+            match rest.peek() {
+                Some(c) if c.matches(ancestor) => {
+                    rest.next();
+                }
+                Some(_) => (),
+                None => break,
+            }
+        }
+        rest.peek().is_none()
+    }
+}
+
+fn parse_compound(s: &str) -> Result<Compound, String> {
+    let mut simple = vec![];
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        let mut name = String::new();
+        while let Some(n) = chars.peek() {
+            if n.is_alphanumeric() || *n == '_' || *n == '-' {
+                name.push(*n);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        let selector = match c {
+            '*' if name.is_empty() => SimpleSelector::Any,
+            '.' if !name.is_empty() => SimpleSelector::Class(name),
+            '#' if !name.is_empty() => SimpleSelector::Id(name),
+            ':' => match name.as_str() {
+                "hover" => SimpleSelector::State(State::Hover),
+                "focus" => SimpleSelector::State(State::Focus),
+                "disabled" => SimpleSelector::State(State::Disabled),
+                "active" => SimpleSelector::State(State::Active),
+                "invalid" => SimpleSelector::State(State::Invalid),
+                _ => return Err(format!("unknown state :{}", name)),
+            },
+            _ if c.is_alphabetic() && simple.is_empty() => {
+                name.insert(0, c);
+                SimpleSelector::Type(name)
+            }
+            _ => return Err(format!("unexpected '{}' in {}", c, s)),
+        };
+        simple.push(selector);
+    }
+    Ok(Compound(simple))
+}
+
+#[derive(Clone, Debug)]
+pub struct Rule {
+    pub selector: Selector,
+    pub properties: Vec<Property>,
+}
+
+//Rules that style elements by their type, class, id and state. The
+//properties an element sets itself win over any rule, and rules win
+//over what an element inherits.
+#[derive(Clone, Debug, Default)]
+pub struct Stylesheet {
+    rules: Vec<Rule>,
+}
+
+lazy_static! {
+    static ref STYLESHEET: Mutex<Arc<Stylesheet>> = Mutex::new(Arc::new(Stylesheet::new()));
+}
+
+impl Stylesheet {
+    pub fn new() -> Stylesheet {
+        Stylesheet { rules: vec![] }
+    }
+
+    pub fn add_rule(
+        &mut self,
+        selector: &str,
+        properties: Vec<Property>,
+    ) -> Result<&mut Stylesheet, String> {
+        let selector = Selector::parse(selector)?;
+        Ok(self.push_rule(selector, properties))
+    }
+
+    pub fn push_rule(&mut self, selector: Selector, properties: Vec<Property>) -> &mut Stylesheet {
+        self.rules.push(Rule {
+            selector,
+            properties,
+        });
+        self
+    }
+
+    pub fn get_rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    //the active stylesheet
+    pub fn current() -> Arc<Stylesheet> {
+        STYLESHEET.lock().unwrap().clone()
+    }
+
+    //replaces the active stylesheet, windows pick it up on their next frame
+    pub fn set(sheet: Stylesheet) {
+        *STYLESHEET.lock().unwrap() = Arc::new(sheet);
+    }
+
+    //matching rules from the least to the most specific, rules of the
+    //same specificity in the order they were added
+    fn matching(&self, node: &StyleNode, ancestors: &[StyleNode]) -> Vec<&Rule> {
+        let mut rules: Vec<&Rule> = self
+            .rules
+            .iter()
+            .filter(|r| r.selector.matches(node, ancestors))
+            .collect();
+        rules.sort_by_key(|r| r.selector.specificity());
+        rules
+    }
+}
+
+//The properties an element of type `kind` renders with, given its own
+//and the ones its parent rendered with. `states` are the states the
+//element is in. Its own properties come first, then the stylesheet,
+//then what it inherits and last the theme.
+pub fn computed(
+    kind: &str,
+    own: &Properties,
+    parent: &Option<Arc<Properties>>,
+    states: Vec<State>,
+) -> Properties {
+    let ancestors = match parent {
+        Some(_p) => _p.get_path().to_vec(),
+        None => vec![],
+    };
+    let node = StyleNode {
+        kind: String::from(kind),
+        id: own.get_id(),
+        classes: own
+            .get_class()
+            .split_whitespace()
+            .map(String::from)
+            .collect(),
+        states,
+    };
+
+    let mut themed = own.clone();
+    Theme::current().apply(kind, &mut themed);
+    let mut props = themed.cascade(parent);
+    let sheet = Stylesheet::current();
+    for rule in sheet.matching(&node, &ancestors) {
+        let state = rule.selector.0.last().and_then(|c| c.state());
+        for property in rule.properties.iter() {
+            let property = match state {
+                Some(_s) => _s.slot(property),
+                None => property.clone(),
+            };
+            if !own.is_set(&property) {
+                props.set(property);
+            }
+        }
+    }
+
+    let mut path = ancestors;
+    path.push(node);
+    props.set_path(path);
+    props
+}
@@ -0,0 +1,335 @@
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+use webrender::api::ColorF;
+
+use crate::gui::properties::{Properties, Property};
+
+//what the user asked the platform for
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Preference {
+    Light,
+    Dark,
+    HighContrast,
+}
+
+//The default properties of every element type, which elements fall back
+//to for anything they, a stylesheet or their parent do not set. The
+//defaults for "*" apply to every type.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    name: String,
+    background: ColorF, //of the windows, behind the root element
+    defaults: HashMap<String, Vec<Property>>,
+}
+
+lazy_static! {
+    static ref THEME: Mutex<Arc<Theme>> = Mutex::new(Arc::new(Theme::light()));
+    //whether the app or the system has picked the theme yet
+    static ref CHOSEN: Mutex<bool> = Mutex::new(false);
+}
+
+impl Theme {
+    pub fn new(name: &str, background: ColorF) -> Theme {
+        Theme {
+            name: String::from(name),
+            background,
+            defaults: HashMap::new(),
+        }
+    }
+
+    pub fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn get_background(&self) -> ColorF {
+        self.background
+    }
+
+    //replaces the defaults of `kind`, an element type such as "Button"
+    pub fn set_defaults(&mut self, kind: &str, properties: Vec<Property>) -> &mut Theme {
+        self.defaults.insert(String::from(kind), properties);
+        self
+    }
+
+    //the defaults of "*" followed by those of `kind`
+    pub fn get_defaults(&self, kind: &str) -> Vec<Property> {
+        let mut properties = vec![];
+        for k in ["*", kind].iter() {
+            if let Some(_p) = self.defaults.get(*k) {
+                properties.extend(_p.iter().cloned());
+            }
+        }
+        properties
+    }
+
+    //the default of `kind` alone for the property `key` stands for
+    pub fn get_default(&self, kind: &str, key: &Property) -> Option<Property> {
+        self.defaults.get(kind)?.iter().find(|p| *p == key).cloned()
+    }
+
+    //fills in what `props` does not set with the defaults of `kind`
+    pub fn apply(&self, kind: &str, props: &mut Properties) {
+        for property in self.get_defaults(kind) {
+            if !props.is_set(&property) {
+                props.set_default(property);
+            }
+        }
+    }
+
+    //the theme in use
+    pub fn current() -> Arc<Theme> {
+        THEME.lock().unwrap().clone()
+    }
+
+    //switches every window to `theme` from its next frame
+    pub fn set(theme: Theme) {
+        *THEME.lock().unwrap() = Arc::new(theme);
+        *CHOSEN.lock().unwrap() = true;
+    }
+
+    //Takes the system theme when the first window opens, unless the app
+    //set one before that.
+    pub(crate) fn init() {
+        if !*CHOSEN.lock().unwrap() {
+            Theme::set(Theme::system());
+        }
+    }
+
+    pub fn light() -> Theme {
+        let mut theme = Theme::new("light", rgb(0.98, 0.98, 0.98));
+        theme
+            .set_defaults(
+                "*",
+                vec![
+                    Property::Color(rgb(0.13, 0.13, 0.13)),
+                    Property::BgColor(clear()),
+                    Property::HoverColor(rgb(0.0, 0.0, 0.0)),
+                    Property::HoverBgColor(clear()),
+                    Property::FocusColor(rgb(0.0, 0.0, 0.0)),
+                    Property::FocusBgColor(clear()),
+                    Property::ActiveColor(rgb(0.0, 0.0, 0.0)),
+                    Property::ActiveBgColor(clear()),
+                    Property::DisabledColor(rgb(0.6, 0.6, 0.6)),
+                    Property::DisabledBgColor(clear()),
+                    Property::InvalidColor(rgb(0.75, 0.0, 0.0)),
+                    Property::InvalidBgColor(rgb(1.0, 0.92, 0.92)),
+                    Property::SelectionBgColor(rgb(0.7, 0.84, 1.0)),
+                    Property::CaretColor(rgb(0.0, 0.0, 0.0)),
+                    Property::GutterColor(rgb(0.55, 0.55, 0.55)),
+                    Property::GutterBgColor(rgb(0.94, 0.94, 0.94)),
+                    Property::LineHighlightColor(rgb(0.96, 0.96, 0.9)),
+                    Property::BracketMatchColor(rgb(0.8, 0.9, 0.8)),
+                ],
+            )
+            .set_defaults(
+                "Button",
+                vec![
+                    Property::Color(rgb(0.2, 0.2, 0.2)),
+                    Property::BgColor(rgb(0.8, 0.9, 0.9)),
+                    Property::HoverColor(rgb(0.1, 0.1, 0.1)),
+                    Property::HoverBgColor(rgb(0.6, 0.7, 0.7)),
+                    Property::ActiveBgColor(rgb(0.5, 0.6, 0.6)),
+                    Property::DisabledBgColor(rgb(0.9, 0.9, 0.9)),
+                ],
+            )
+            .set_defaults(
+                "TextBox",
+                vec![
+                    Property::HoverBgColor(rgb(0.94, 0.94, 0.94)),
+                    Property::FocusBgColor(rgb(1.0, 1.0, 1.0)),
+                ],
+            )
+            .set_defaults(
+                "SplitPane",
+                vec![
+                    Property::Color(rgb(0.8, 0.8, 0.8)),
+                    Property::HoverColor(rgb(0.6, 0.6, 0.6)),
+                    Property::ActiveColor(rgb(0.5, 0.5, 0.5)),
+                ],
+            );
+        theme
+    }
+
+    pub fn dark() -> Theme {
+        let mut theme = Theme::new("dark", rgb(0.2, 0.2, 0.2));
+        theme
+            .set_defaults(
+                "*",
+                vec![
+                    Property::Color(rgb(0.8, 0.8, 0.8)),
+                    Property::BgColor(clear()),
+                    Property::HoverColor(rgb(0.9, 0.9, 0.9)),
+                    Property::HoverBgColor(clear()),
+                    Property::FocusColor(rgb(1.0, 1.0, 1.0)),
+                    Property::FocusBgColor(clear()),
+                    Property::ActiveColor(rgb(1.0, 1.0, 1.0)),
+                    Property::ActiveBgColor(clear()),
+                    Property::DisabledColor(rgb(0.5, 0.5, 0.5)),
+                    Property::DisabledBgColor(clear()),
+                    Property::InvalidColor(rgb(1.0, 0.55, 0.55)),
+                    Property::InvalidBgColor(rgb(0.3, 0.12, 0.12)),
+                    Property::SelectionBgColor(rgb(0.15, 0.3, 0.5)),
+                    Property::CaretColor(rgb(1.0, 1.0, 1.0)),
+                    Property::GutterColor(rgb(0.5, 0.5, 0.5)),
+                    Property::GutterBgColor(rgb(0.17, 0.17, 0.17)),
+                    Property::LineHighlightColor(rgb(0.25, 0.25, 0.22)),
+                    Property::BracketMatchColor(rgb(0.2, 0.35, 0.25)),
+                ],
+            )
+            .set_defaults(
+                "Button",
+                vec![
+                    Property::BgColor(rgb(0.3, 0.35, 0.35)),
+                    Property::HoverBgColor(rgb(0.38, 0.45, 0.45)),
+                    Property::ActiveBgColor(rgb(0.25, 0.3, 0.3)),
+                    Property::DisabledBgColor(rgb(0.25, 0.25, 0.25)),
+                ],
+            )
+            .set_defaults(
+                "TextBox",
+                vec![
+                    Property::HoverBgColor(rgb(0.25, 0.25, 0.25)),
+                    Property::FocusBgColor(rgb(0.1, 0.1, 0.1)),
+                ],
+            )
+            .set_defaults(
+                "SplitPane",
+                vec![
+                    Property::Color(rgb(0.3, 0.3, 0.3)),
+                    Property::HoverColor(rgb(0.45, 0.45, 0.45)),
+                    Property::ActiveColor(rgb(0.55, 0.55, 0.55)),
+                ],
+            );
+        theme
+    }
+
+    //black and white with yellow for whatever is under the cursor,
+    //focused or pressed
+    pub fn high_contrast() -> Theme {
+        let black = rgb(0.0, 0.0, 0.0);
+        let white = rgb(1.0, 1.0, 1.0);
+        let yellow = rgb(1.0, 1.0, 0.0);
+        let mut theme = Theme::new("high-contrast", black);
+        theme
+            .set_defaults(
+                "*",
+                vec![
+                    Property::Color(white),
+                    Property::BgColor(clear()),
+                    Property::HoverColor(black),
+                    Property::HoverBgColor(yellow),
+                    Property::FocusColor(black),
+                    Property::FocusBgColor(yellow),
+                    Property::ActiveColor(black),
+                    Property::ActiveBgColor(white),
+                    Property::DisabledColor(rgb(0.0, 1.0, 0.0)),
+                    Property::DisabledBgColor(black),
+                    Property::InvalidColor(white),
+                    Property::InvalidBgColor(rgb(0.6, 0.0, 0.0)),
+                    Property::SelectionBgColor(rgb(0.0, 0.0, 1.0)),
+                    Property::CaretColor(yellow),
+                    Property::GutterColor(white),
+                    Property::GutterBgColor(black),
+                    Property::LineHighlightColor(rgb(0.2, 0.2, 0.2)),
+                    Property::BracketMatchColor(rgb(0.0, 0.0, 1.0)),
+                ],
+            )
+            .set_defaults("Button", vec![Property::BgColor(rgb(0.0, 0.0, 0.5))])
+            .set_defaults(
+                "SplitPane",
+                vec![
+                    Property::Color(white),
+                    Property::HoverColor(yellow),
+                    Property::ActiveColor(yellow),
+                ],
+            );
+        theme
+    }
+
+    //The bundled theme matching what the platform asks for, light if it
+    //does not say. It runs `detect`, so it is best not called every frame.
+    pub fn system() -> Theme {
+        match detect() {
+            Some(Preference::Dark) => Theme::dark(),
+            Some(Preference::HighContrast) => Theme::high_contrast(),
+            _ => Theme::light(),
+        }
+    }
+}
+
+fn rgb(r: f32, g: f32, b: f32) -> ColorF {
+    ColorF::new(r, g, b, 1.0)
+}
+
+fn clear() -> ColorF {
+    ColorF::new(0.0, 0.0, 0.0, 0.0)
+}
+
+//output of a command, None if it could not be run or failed
+fn run(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if output.status.success() {
+        Some(
+            String::from_utf8_lossy(&output.stdout)
+                .trim()
+                .to_lowercase(),
+        )
+    } else {
+        None
+    }
+}
+
+//The light or dark preference of the desktop where it can be asked for,
+//gnome settings on linux, the interface style on macos and the app mode
+//on windows. Every call asks again by running `gsettings`, `defaults` or
+//`reg`, which takes a few milliseconds each time.
+pub fn detect() -> Option<Preference> {
+    if cfg!(target_os = "linux") {
+        let gs = |key: &str| {
+            let mut parts = key.rsplitn(2, '.');
+            let name = parts.next()?;
+            let schema = parts.next()?;
+            run("gsettings", &["get", schema, name])
+        };
+        if gs("org.gnome.desktop.a11y.interface.high-contrast") == Some(String::from("true")) {
+            return Some(Preference::HighContrast);
+        }
+        if let Some(scheme) = gs("org.gnome.desktop.interface.color-scheme") {
+            if scheme.contains("dark") {
+                return Some(Preference::Dark);
+            }
+            if scheme.contains("light") {
+                return Some(Preference::Light);
+            }
+        }
+        if let Ok(gtk) = std::env::var("GTK_THEME") {
+            return Some(if gtk.to_lowercase().contains("dark") {
+                Preference::Dark
+            } else {
+                Preference::Light
+            });
+        }
+        match gs("org.gnome.desktop.interface.gtk-theme") {
+            Some(_t) if _t.contains("dark") => Some(Preference::Dark),
+            Some(_) => Some(Preference::Light),
+            None => None,
+        }
+    } else if cfg!(target_os = "macos") {
+        //only set when the dark appearance is on
+        match run("defaults", &["read", "-g", "AppleInterfaceStyle"]) {
+            Some(_s) if _s.contains("dark") => Some(Preference::Dark),
+            _ => Some(Preference::Light),
+        }
+    } else if cfg!(target_os = "windows") {
+        let key = "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize";
+        match run("reg", &["query", key, "/v", "AppsUseLightTheme"]) {
+            Some(_s) if _s.contains("0x0") => Some(Preference::Dark),
+            Some(_) => Some(Preference::Light),
+            None => None,
+        }
+    } else {
+        None
+    }
+}
@@ -5,16 +5,19 @@ use glutin;
 use webrender;
 use webrender::api::*;
 
-use crate::elements::{Element, PrimitiveEvent};
+use crate::elements::{validate_element, Element, PrimitiveEvent};
 use crate::gui::font;
+use crate::gui::overlay::Overlay;
 use crate::gui::properties;
+use crate::gui::theme::Theme;
 use crate::util::*;
 
+use std::collections::HashMap;
 use std::mem;
 use std::ops::DerefMut;
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 use std::fmt;
 
 impl Into<properties::Position> for glutin::dpi::LogicalPosition {
@@ -334,6 +337,8 @@ impl Internals {
                             cursor_position,
                         );
                     }
+                    //for elements that scroll what they draw themselves
+                    events.push(PrimitiveEvent::Wheel(dx, dy));
 
                     //println!("scrolling {} {}",dx,dy);
                 }
@@ -407,6 +412,10 @@ pub struct Window {
     id_generator: properties::IdGenerator,
     internals: Option<Internals>,
     tags: Vec<ItemTag>,
+    overlay: Overlay,
+    min_size: (f64, f64),
+    ime_spot: Option<properties::Position>,
+    key: u64, //tells apart what is queued for this window
 }
 
 impl fmt::Debug for Window {
@@ -427,6 +436,10 @@ impl Window {
             id_generator,
             internals: None,
             tags: vec![],
+            overlay: Overlay::new(),
+            min_size: (0.0, 0.0),
+            ime_spot: None,
+            key: next_key(),
         };
 
         _w.start_window();
@@ -435,6 +448,7 @@ impl Window {
     }
 
     fn start_window(&mut self) {
+        Theme::init();
         self.internals = Some(Internals::new(&self.name, self.width, self.height));
     }
 
@@ -506,37 +520,58 @@ impl Window {
                 }
                 PrimitiveEvent::SetFocus(b) => {
                     if !*b {
-                        self.root.lock().unwrap().on_primitive_event(&[], e.clone());
+                        //escape closes the topmost popup before anything loses focus
+                        if !self.overlay.close_top() {
+                            self.root.lock().unwrap().on_primitive_event(&[], e.clone());
+                        }
                     } else {
+                        match self.overlay.on_pointer_event(&tags, e) {
+                            Some(root_tags) => {
+                                self.root
+                                    .lock()
+                                    .unwrap()
+                                    .on_primitive_event(&root_tags, e.clone());
+                            }
+                            None => {
+                                self.root
+                                    .lock()
+                                    .unwrap()
+                                    .on_primitive_event(&[], PrimitiveEvent::SetFocus(false));
+                            }
+                        }
+                    }
+                }
+                PrimitiveEvent::Button(_, _, _, _) => {
+                    if let Some(root_tags) = self.overlay.on_pointer_event(&tags, e) {
                         self.root
                             .lock()
                             .unwrap()
-                            .on_primitive_event(&tags, e.clone());
+                            .on_primitive_event(&root_tags, e.clone());
                     }
                 }
-                PrimitiveEvent::Button(_, _, _, _) => {
-                    self.root
-                        .lock()
-                        .unwrap()
-                        .on_primitive_event(&tags, e.clone());
-                }
                 PrimitiveEvent::Char(_) => {
-                    self.root
-                        .lock()
-                        .unwrap()
-                        .on_primitive_event(&tags, e.clone());
+                    if !self.overlay.on_event(e) {
+                        self.root
+                            .lock()
+                            .unwrap()
+                            .on_primitive_event(&tags, e.clone());
+                    }
                 }
-                PrimitiveEvent::CursorMoved(_) => {
-                    self.root
-                        .lock()
-                        .unwrap()
-                        .on_primitive_event(&tags, e.clone());
+                PrimitiveEvent::CursorMoved(_) | PrimitiveEvent::Wheel(..) => {
+                    if let Some(root_tags) = self.overlay.on_pointer_event(&tags, e) {
+                        self.root
+                            .lock()
+                            .unwrap()
+                            .on_primitive_event(&root_tags, e.clone());
+                    }
                 }
                 PrimitiveEvent::KeyInput(_, _, _, _) => {
-                    self.root
-                        .lock()
-                        .unwrap()
-                        .on_primitive_event(&tags, e.clone());
+                    if !self.overlay.on_event(e) {
+                        self.root
+                            .lock()
+                            .unwrap()
+                            .on_primitive_event(&tags, e.clone());
+                    }
                 }
                 _ => (),
             }
@@ -545,6 +580,7 @@ impl Window {
 
     pub fn tick(&mut self) -> bool {
         let exit = false;
+        *CURRENT.lock().unwrap() = Some(self.key);
 
         let events;
         let mut dpi;
@@ -568,7 +604,8 @@ impl Window {
             self.root
                 .lock()
                 .unwrap()
-                .on_primitive_event(&[], PrimitiveEvent::HoverBegin(new_tags));
+                .on_primitive_event(&[], PrimitiveEvent::HoverBegin(new_tags.clone()));
+            self.overlay.on_event(&PrimitiveEvent::HoverBegin(new_tags));
         }
 
         if !old_tags.is_empty() {
@@ -576,7 +613,8 @@ impl Window {
             self.root
                 .lock()
                 .unwrap()
-                .on_primitive_event(&[], PrimitiveEvent::HoverEnd(old_tags));
+                .on_primitive_event(&[], PrimitiveEvent::HoverEnd(old_tags.clone()));
+            self.overlay.on_event(&PrimitiveEvent::HoverEnd(old_tags));
         }
 
         //if events.len() > 0 {
@@ -584,6 +622,7 @@ impl Window {
         //}
 
         self.action_events(events, &tags);
+        self.overlay.update(self.key);
 
         let mut window: Option<glutin::WindowedContext<glutin::NotCurrent>> = None;
 
@@ -628,8 +667,29 @@ impl Window {
         let framebuffer_size = framebuffer_size.unwrap();
         let layout_size = layout_size.unwrap();
 
+        IME_SPOT.lock().unwrap().remove(&self.key);
         self.render_root(&api, &mut builder, font_store, dpi as f32);
 
+        let ime_spot = IME_SPOT.lock().unwrap().remove(&self.key);
+        if ime_spot.is_some() && ime_spot != self.ime_spot {
+            if let Some(ref p) = ime_spot {
+                window
+                    .window()
+                    .set_ime_spot(glutin::dpi::LogicalPosition::new(p.x as f64, p.y as f64));
+            }
+            self.ime_spot = ime_spot;
+        }
+
+        //the window never gets too small for the minimums of what is in it
+        let (min_w, min_h) = self.root.lock().unwrap().get_min_size();
+        let min_size = (f64::from(min_w), f64::from(min_h));
+        if min_size != self.min_size {
+            self.min_size = min_size;
+            window
+                .window()
+                .set_min_dimensions(Some(glutin::dpi::LogicalSize::new(min_size.0, min_size.1)));
+        }
+
         if let Some(ref mut i) = self.internals {
             txn.set_window_parameters(
                 framebuffer_size,
@@ -639,6 +699,19 @@ impl Window {
             );
 
             txn.set_display_list(i.epoch, None, layout_size, builder.finalize(), true);
+
+            let reveals: Vec<_> = REVEAL.lock().unwrap().drain(..).collect();
+            if !reveals.is_empty() {
+                let states = i.api.get_scroll_node_state(i.document_id);
+                for (id, rect, w, h) in reveals {
+                    let offset = match states.iter().find(|s| s.id == id) {
+                        Some(state) => state.scroll_offset,
+                        None => LayoutVector2D::new(0.0, 0.0),
+                    };
+                    let origin = reveal_origin(offset, &rect, w, h);
+                    txn.scroll_node_with_id(origin, id, ScrollClamping::ToContentBounds);
+                }
+            }
             //txn.set_root_pipeline(i.pipeline_id);
             txn.generate_frame();
             i.api.send_transaction(i.document_id, txn);
@@ -658,6 +731,7 @@ impl Window {
             _ => ()
         }
 
+        *CURRENT.lock().unwrap() = None;
         exit
     }
 
@@ -671,6 +745,12 @@ impl Window {
         let mut gen = self.id_generator.clone();
         gen.zero();
 
+        properties::Viewport::set(properties::Viewport {
+            w: self.width as f32,
+            h: self.height as f32,
+            rem: self.root.lock().unwrap().get_properties().get_size() as f32,
+        });
+
         let info = LayoutPrimitiveInfo::new((0.0, 0.0).by(self.width as f32, self.height as f32));
         builder.push_stacking_context(
             &info,
@@ -681,7 +761,12 @@ impl Window {
             RasterSpace::Screen,
         );
 
-        self.root.lock().unwrap().render(
+        //the theme's background, so switching themes does not need a new renderer
+        builder.push_rect(&info, Theme::current().get_background());
+
+        let mut root = self.root.lock().unwrap();
+        validate_element(&*root);
+        root.render(
             api,
             builder,
             properties::Extent {
@@ -695,6 +780,17 @@ impl Window {
             None,
             &mut gen,
         );
+        drop(root);
+
+        self.overlay.render(
+            api,
+            builder,
+            font_store,
+            &mut gen,
+            self.width as f32,
+            self.height as f32,
+            dpi,
+        );
 
         builder.pop_stacking_context();
     }
@@ -712,6 +808,85 @@ impl Drop for Window {
 lazy_static! {
     static ref TOADD: Mutex<Vec<(Arc<Mutex<dyn Element>>, String, f64, f64)>> = Mutex::new(vec![]);
     static ref TODEL: Mutex<Vec<glutin::WindowId>> = Mutex::new(vec![]);
+    static ref WAKE: Mutex<Option<Instant>> = Mutex::new(None);
+    static ref CURRENT: Mutex<Option<u64>> = Mutex::new(None);
+    static ref NEXT_KEY: Mutex<u64> = Mutex::new(0);
+    static ref IME_SPOT: Mutex<HashMap<u64, properties::Position>> = Mutex::new(HashMap::new());
+    static ref REVEAL: Mutex<Vec<(ExternalScrollId, properties::Extent, f32, f32)>> =
+        Mutex::new(vec![]);
+}
+
+fn next_key() -> u64 {
+    let mut key = NEXT_KEY.lock().unwrap();
+    *key += 1;
+    *key
+}
+
+//The window being ticked, whose events are handled and which is being
+//rendered. None between ticks, such as on another thread.
+pub fn current() -> Option<u64> {
+    *CURRENT.lock().unwrap()
+}
+
+//Where the input method shows its candidates, under the caret of the
+//element being typed into. Elements call it while their window renders.
+pub fn set_ime_spot(x: f32, y: f32) {
+    if let Some(key) = current() {
+        IME_SPOT
+            .lock()
+            .unwrap()
+            .insert(key, properties::Position { x, y });
+    }
+}
+
+//Scrolls the scroll frame `id`, which shows `w` by `h` of its content,
+//so that `rect` in the content can be seen. It is done once the frame
+//being rendered is sent.
+pub fn reveal_in_frame(id: ExternalScrollId, rect: properties::Extent, w: f32, h: f32) {
+    REVEAL.lock().unwrap().push((id, rect, w, h));
+}
+
+//where a frame scrolled by `offset` has to be scrolled to for `rect` to
+//be seen, moving as little as it can
+fn reveal_origin(offset: LayoutVector2D, rect: &properties::Extent, w: f32, h: f32) -> LayoutPoint {
+    let mut x = -offset.x;
+    let mut y = -offset.y;
+    if rect.x < x {
+        x = rect.x;
+    } else if rect.x + rect.w > x + w {
+        x = rect.x + rect.w - w;
+    }
+    if rect.y < y {
+        y = rect.y;
+    } else if rect.y + rect.h > y + h {
+        y = rect.y + rect.h - h;
+    }
+    LayoutPoint::new(x, y)
+}
+
+//Asks for the windows to be rendered again by `at`, sooner than the next
+//frame if need be, for what changes with time like a blinking caret.
+pub fn wake_at(at: Instant) {
+    let mut wake = WAKE.lock().unwrap();
+    match *wake {
+        Some(t) if t <= at => (),
+        _ => *wake = Some(at),
+    }
+}
+
+//the time until the next frame or the earliest wake_at, if that is sooner
+fn sleep_time(frame: Duration) -> Duration {
+    match WAKE.lock().unwrap().take() {
+        Some(at) => {
+            let now = Instant::now();
+            if at > now {
+                frame.min(at - now)
+            } else {
+                Duration::from_millis(0)
+            }
+        }
+        None => frame,
+    }
 }
 
 pub struct Manager {
@@ -787,9 +962,9 @@ impl Manager {
                 if t > fps {
                     t = fps;
                 }
-                thread::sleep(Duration::from_millis(fps - t));
+                thread::sleep(sleep_time(Duration::from_millis(fps - t)));
             } else {
-                thread::sleep(Duration::from_millis(fps));
+                thread::sleep(sleep_time(Duration::from_millis(fps)));
             }
         }
     }
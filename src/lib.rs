@@ -11,6 +11,7 @@ extern crate winit;
 extern crate lazy_static;
 extern crate harfbuzz_sys;
 extern crate itertools;
+extern crate regex;
 extern crate unicode_bidi;
 
 pub mod data;